@@ -3,9 +3,15 @@ use std::collections::HashMap;
 use aws_lambda_events::event::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse};
 use lambda_runtime::{Context, Handler};
 use lambda_runtime::error::HandlerError;
+use serde_json::Value;
 use crate::request::SrvrlsRequest;
-use crate::components::SrvrlsError;
+use crate::components::{HttpMethod, SrvrlsError};
+#[cfg(feature = "compression")]
+use crate::compression::{compress, Compression};
+use crate::events::{HttpApiRequest, HttpApiResponse, http_api_proxy_request};
 use crate::response::SrvrlsResponse;
+use crate::serializer::{JsonSerializer, negotiate, SrvrlsSerializer};
+use crate::warmup::{log_warm_up, WarmUp};
 
 /// This trait should be implemented by your application to handle inbound events. The values for
 /// these responses (e.g., status code, body, headers) will be mapped to the API Gateway response.
@@ -44,40 +50,85 @@ pub trait SrvrlsApplication {
 }
 
 type HeaderInterceptor = Box<dyn Fn(HashMap<String, String>) -> HashMap<String, String>>;
+type MultiValueHeaderInterceptor = Box<dyn Fn(HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>>>;
+type OverflowHook = Box<dyn Fn(&ApiGatewayProxyResponse) -> Result<SrvrlsResponse, SrvrlsError>>;
+type WarmUpHook<T> = Box<dyn FnMut(&mut T)>;
 
 /// Srvrls wraps your application that implements `SrvrlsApplication` and interfaces with the
 /// AWS Lambda to handle the logic of translating requests and responses.
 pub struct Srvrls<T: SrvrlsApplication> {
     application: T,
     pub(crate) response_header_interceptor: HeaderInterceptor,
+    response_multi_value_header_interceptor: MultiValueHeaderInterceptor,
+    automatic_head: bool,
+    #[cfg(feature = "compression")]
+    compression: Option<Compression>,
+    response_size_limit: usize,
+    response_overflow_hook: Option<OverflowHook>,
+    serializers: Vec<Box<dyn SrvrlsSerializer>>,
+    warm_up: WarmUp,
+    warm_up_hook: Option<WarmUpHook<T>>,
 }
 
 impl<T: SrvrlsApplication> Handler<ApiGatewayProxyRequest, ApiGatewayProxyResponse, HandlerError> for Srvrls<T> {
     fn run(&mut self, event: ApiGatewayProxyRequest, _ctx: Context) -> Result<ApiGatewayProxyResponse, HandlerError> {
-        let request: SrvrlsRequest = event.into();
-        match self.application.handle(request) {
-            Ok(response) => {
-                let headers = (self.response_header_interceptor)(response.headers);
-                let body = response.body;
-                Ok(Srvrls::<T>::response(i64::from(response.status_code), body, headers))
-            }
-            Err(e) => {
-                let headers = (self.response_header_interceptor)(HashMap::new());
-                match e {
-                    SrvrlsError::BadRequest(body) => Ok(Srvrls::<T>::response(400, Some(body), headers)),
-                    SrvrlsError::BadRequestNoMessage() => Ok(Srvrls::<T>::response(400, None, headers)),
-                    SrvrlsError::BadRequestWithSimpleMessage(simple_message) => {
-                        let payload = serde_json::to_string(&SrvrlsResponse::simple_error(simple_message))?;
-                        Ok(Srvrls::<T>::response(400, Some(payload), headers))
-                    }
-                    SrvrlsError::Unauthorized => Ok(Srvrls::<T>::response(401, None, headers)),
-                    SrvrlsError::Forbidden => Ok(Srvrls::<T>::response(403, None, headers)),
-                    SrvrlsError::NotFound => Ok(Srvrls::<T>::response(404, None, headers)),
-                    SrvrlsError::MethodNotAllowed => Ok(Srvrls::<T>::response(405, None, headers)),
-                    SrvrlsError::InternalServerError => Ok(Srvrls::<T>::response(500, None, headers)),
-                }
-            }
+        let mut request: SrvrlsRequest = event.into();
+        let head_request = self.automatic_head && request.method == HttpMethod::HEAD;
+        if head_request {
+            request.method = HttpMethod::GET;
         }
+        let accept = request.header("Accept");
+        let accept_encoding = request.header("Accept-Encoding");
+        let result = self.application.handle(request)
+            .and_then(|response| self.gateway_response(&accept, &accept_encoding, response));
+        let mut response = match result {
+            Ok(response) => response,
+            Err(e) => self.error_response(e)?,
+        };
+        if head_request {
+            let content_length = match (response.body.take(), response.is_base64_encoded.take()) {
+                (Some(body), Some(true)) => base64::decode(&body).map_or(body.len(), |decoded| decoded.len()),
+                (Some(body), _) => body.len(),
+                (None, _) => 0,
+            };
+            response.headers.insert("Content-Length".to_string(), content_length.to_string());
+        }
+        let size = serde_json::to_vec(&response)?.len();
+        if size > self.response_size_limit {
+            return self.overflow_response(&accept, &accept_encoding, &response, size);
+        }
+        Ok(response)
+    }
+}
+
+/// `SrvrlsHttpApi` wraps a `Srvrls` instance and interfaces with AWS Lambda for API Gateway HTTP
+/// API (payload format version 2.0) events. These are handled identically to REST API events, with
+/// any cookies returned in the `cookies` field of the response. Lambda Function URL invocations
+/// share this payload (without a route key or stage) and are served the same way, with the caller
+/// of an `AWS_IAM` Function URL available in `SrvrlsRequest::iam_identity`.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let http_api = SrvrlsHttpApi::new(build_srvrls());
+///     lambda!(http_api);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsHttpApi<T: SrvrlsApplication> {
+    srvrls: Srvrls<T>,
+}
+
+impl<T: SrvrlsApplication> SrvrlsHttpApi<T> {
+    /// Create a new `SrvrlsHttpApi` instance for the provided `Srvrls` instance.
+    pub fn new(srvrls: Srvrls<T>) -> Self {
+        SrvrlsHttpApi { srvrls }
+    }
+}
+
+impl<T: SrvrlsApplication> Handler<HttpApiRequest, HttpApiResponse, HandlerError> for SrvrlsHttpApi<T> {
+    fn run(&mut self, event: HttpApiRequest, ctx: Context) -> Result<HttpApiResponse, HandlerError> {
+        let response = self.srvrls.run(http_api_proxy_request(event), ctx)?;
+        Ok(response.into())
     }
 }
 
@@ -123,7 +174,20 @@ impl<T: SrvrlsApplication> Srvrls<T> {
     /// [lamba runtime deployment notes](https://github.com/awslabs/aws-lambda-rust-runtime#deployment).
     pub fn new(application: T) -> Self {
         let response_header_interceptor = Box::new(|_h: HashMap<String, String>| HashMap::new());
-        Srvrls { application, response_header_interceptor }
+        let response_multi_value_header_interceptor = Box::new(|h: HashMap<String, Vec<String>>| h);
+        Srvrls {
+            application,
+            response_header_interceptor,
+            response_multi_value_header_interceptor,
+            automatic_head: false,
+            #[cfg(feature = "compression")]
+            compression: None,
+            response_size_limit: 6 * 1024 * 1024,
+            response_overflow_hook: None,
+            serializers: vec![Box::new(JsonSerializer {})],
+            warm_up: WarmUp::default(),
+            warm_up_hook: None,
+        }
     }
 
     /// This function allows for adding a closure that will function as a header interceptor.
@@ -156,6 +220,309 @@ impl<T: SrvrlsApplication> Srvrls<T> {
         self.response_header_interceptor = header_interceptor;
     }
 
+    /// This function allows for adding a closure that will function as an interceptor for headers
+    /// with multiple values, including any `Set-Cookie` headers. All responses, including error
+    /// responses, will then have their multi-value headers enhanced by this interceptor.
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # struct App {}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{});
+    ///     let header_interceptor = |mut h: HashMap<String,Vec<String>>| {
+    ///         h.entry("Vary".to_string()).or_default().push("Accept".to_string());
+    ///         h
+    ///     };
+    ///     srvrls.with_response_multi_value_header_interceptor(Box::new(header_interceptor));
+    ///     srvrls
+    /// }
+    ///```
+    pub fn with_response_multi_value_header_interceptor(&mut self, header_interceptor: MultiValueHeaderInterceptor) {
+        self.response_multi_value_header_interceptor = header_interceptor;
+    }
+
+    /// Adds a serializer used to render response payloads, replacing any serializer already added
+    /// for the same content type. The serializer is selected using the `Accept` header of the
+    /// request, with a 406 - Not Acceptable returned if no serializer matches. By default only a
+    /// `JsonSerializer` is provided, this is used for requests without an `Accept` header. The CBOR,
+    /// MessagePack, YAML and CSV serializers are enabled by the `cbor`, `msgpack`, `yaml` and `csv`
+    /// features.
+    ///
+    /// A `Content-Type` header is set on any response with a payload unless one was already set
+    /// by the application or header interceptor, in which case the body is sent as JSON.
+    /// ```rust
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # use srvrls::serializer::PrettyJsonSerializer;
+    /// # struct App {}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{});
+    ///     srvrls.with_serializer(Box::new(PrettyJsonSerializer {}));
+    ///     srvrls
+    /// }
+    /// ```
+    pub fn with_serializer(&mut self, serializer: Box<dyn SrvrlsSerializer>) {
+        let existing = self.serializers.iter()
+            .position(|s| s.content_type().eq_ignore_ascii_case(serializer.content_type()));
+        match existing {
+            Some(position) => self.serializers[position] = serializer,
+            None => self.serializers.push(serializer),
+        }
+    }
+
+    /// Enables compression of response bodies larger than the configured threshold, using the
+    /// preferred encoding the client accepts in its' `Accept-Encoding` header. Compressed bodies are
+    /// base64 encoded and sent with `Content-Encoding` and `Vary: Accept-Encoding` headers.
+    /// Responses with content types that are already compressed (e.g., images or zip files), or
+    /// that already have a `Content-Encoding`, are never compressed. This requires the `compression`
+    /// feature.
+    /// ```rust
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::compression::Compression;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # struct App {}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{});
+    ///     srvrls.with_compression(Compression::default());
+    ///     srvrls
+    /// }
+    /// ```
+    #[cfg(feature = "compression")]
+    pub fn with_compression(&mut self, compression: Compression) {
+        self.compression = Some(compression);
+    }
+
+    /// Sets the maximum size in bytes of the serialized response sent to AWS Lambda, defaults to the
+    /// 6 MB Lambda response payload limit. Responses exceeding this are passed to the overflow hook
+    /// if one is configured, otherwise (or if the replacement is also too large) a 500 - Internal
+    /// Server Error is returned with a JSON `SimpleError` body describing the size of the response.
+    pub fn with_response_size_limit(&mut self, response_size_limit: usize) {
+        self.response_size_limit = response_size_limit;
+    }
+
+    /// This function allows for adding a closure that replaces any response exceeding the response
+    /// size limit, e.g., by storing the body elsewhere and redirecting to it. The closure receives
+    /// the rendered response that was too large.
+    /// ```rust
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # struct App {}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// # fn presigned_url(body: &Option<String>) -> String { "https://bucket.s3.amazonaws.com/report".to_string() }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{});
+    ///     srvrls.with_response_header_interceptor(Box::new(|h| h));
+    ///     srvrls.with_response_overflow_hook(Box::new(|oversized| {
+    ///         let mut response = SrvrlsResponse::see_other();
+    ///         response.headers.insert("Location".to_string(), presigned_url(&oversized.body));
+    ///         Ok(response)
+    ///     }));
+    ///     srvrls
+    /// }
+    /// ```
+    pub fn with_response_overflow_hook(&mut self, response_overflow_hook: OverflowHook) {
+        self.response_overflow_hook = Some(response_overflow_hook);
+    }
+
+    /// When enabled, `HEAD` requests are passed to your application as `GET` requests and the
+    /// response body is then removed, keeping the headers and setting the `Content-Length` of the
+    /// body that would have been sent. This mirrors the behavior of most web servers.
+    /// ```rust
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # struct App {}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{});
+    ///     srvrls.with_automatic_head(true);
+    ///     srvrls
+    /// }
+    /// ```
+    pub fn with_automatic_head(&mut self, automatic_head: bool) {
+        self.automatic_head = automatic_head;
+    }
+
+    /// Sets the payloads recognized as warm-up (keep-alive) pings, by default these are the
+    /// `serverless-plugin-warmup` payload, scheduled EventBridge events and empty events. Warm-up
+    /// pings are logged and answered without calling your application.
+    ///
+    /// Warm-up pings are only recognized in the raw event, so this requires serving your
+    /// application through `SrvrlsDispatcher::with_http` rather than as a typed handler.
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # use srvrls::warmup::WarmUp;
+    /// # struct App {}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{});
+    ///     srvrls.with_warm_up(WarmUp { payloads: vec![json!({"source": "keep-alive"})] });
+    ///     srvrls
+    /// }
+    /// ```
+    pub fn with_warm_up(&mut self, warm_up: WarmUp) {
+        self.warm_up = warm_up;
+    }
+
+    /// This function allows for adding a closure that is run with your application on each warm-up
+    /// ping, e.g., to prime database connections before the next request arrives.
+    /// ```rust
+    /// # use srvrls::application::Srvrls;
+    /// # use srvrls::application::SrvrlsApplication;
+    /// # use srvrls::components::SrvrlsError;
+    /// # use srvrls::request::SrvrlsRequest;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// # struct App {connected: bool}
+    /// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+    /// #         Ok(SrvrlsResponse::ok_empty())
+    /// #     }
+    /// # }
+    /// fn build_srvrls() -> Srvrls<App> {
+    ///     let mut srvrls = Srvrls::new(App{connected: false});
+    ///     srvrls.with_warm_up_hook(Box::new(|app| app.connected = true));
+    ///     srvrls
+    /// }
+    /// ```
+    pub fn with_warm_up_hook(&mut self, warm_up_hook: WarmUpHook<T>) {
+        self.warm_up_hook = Some(warm_up_hook);
+    }
+
+    /// Answers the event if it is a warm-up ping, returning whether it was.
+    pub(crate) fn warm_up(&mut self, event: &Value, ctx: &Context) -> bool {
+        if !self.warm_up.matches(event) {
+            return false;
+        }
+        log_warm_up(event, ctx);
+        if let Some(hook) = &mut self.warm_up_hook {
+            hook(&mut self.application);
+        }
+        true
+    }
+
+    #[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+    fn gateway_response(&self, accept: &str, accept_encoding: &str, response: SrvrlsResponse) -> Result<ApiGatewayProxyResponse, SrvrlsError> {
+        let mut headers = (self.response_header_interceptor)(response.headers);
+        let mut body = response.body;
+        let mut is_base64_encoded = response.is_base64_encoded;
+        if let (true, Some(json), None) = (response.is_payload, &body, header_value(&headers, "Content-Type")) {
+            let serializer = negotiate(accept, &self.serializers).ok_or(SrvrlsError::NotAcceptable)?;
+            let serialized = serializer.serialize_json(json)?;
+            is_base64_encoded = serializer.is_binary();
+            body = Some(match is_base64_encoded {
+                true => base64::encode(serialized),
+                false => String::from_utf8_lossy(&serialized).into_owned(),
+            });
+            headers.insert("Content-Type".to_string(), serializer.content_type().to_string());
+        }
+        let mut multi_value_headers = response.multi_value_headers;
+        #[cfg(feature = "compression")]
+        self.compress_body(accept_encoding, &mut headers, &mut multi_value_headers, &mut body, &mut is_base64_encoded)?;
+        if !response.cookies.is_empty() {
+            let set_cookies = response.cookies.iter().map(ToString::to_string);
+            multi_value_headers.entry("Set-Cookie".to_string()).or_default().extend(set_cookies);
+        }
+        let mut gateway_response = Srvrls::<T>::response(i64::from(response.status_code), body, headers);
+        gateway_response.multi_value_headers = (self.response_multi_value_header_interceptor)(multi_value_headers);
+        if is_base64_encoded {
+            gateway_response.is_base64_encoded = Some(true);
+        }
+        Ok(gateway_response)
+    }
+
+    #[cfg(feature = "compression")]
+    fn compress_body(&self, accept_encoding: &str, headers: &mut HashMap<String, String>, multi_value_headers: &mut HashMap<String, Vec<String>>,
+                     body: &mut Option<String>, is_base64_encoded: &mut bool) -> Result<(), SrvrlsError> {
+        if let (Some(compression), Some(uncompressed)) = (&self.compression, &*body) {
+            let uncompressed = match *is_base64_encoded {
+                true => base64::decode(uncompressed).map_err(|_| SrvrlsError::InternalServerError)?,
+                false => uncompressed.clone().into_bytes(),
+            };
+            let content_type = header_value(headers, "Content-Type").unwrap_or_default();
+            let content_encoding = header_value(headers, "Content-Encoding");
+            if content_encoding.is_none() && compression.applies_to(&content_type, uncompressed.len()) {
+                match header_value(headers, "Vary") {
+                    Some(vary) => { headers.insert(header_name(headers, "Vary"), format!("{}, Accept-Encoding", vary)); }
+                    None => multi_value_headers.entry("Vary".to_string()).or_default().push("Accept-Encoding".to_string()),
+                }
+                if let Some(encoding) = compression.select(accept_encoding) {
+                    *body = Some(base64::encode(compress(encoding, &uncompressed)?));
+                    *is_base64_encoded = true;
+                    headers.insert("Content-Encoding".to_string(), encoding.token().to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn overflow_response(&self, accept: &str, accept_encoding: &str, oversized: &ApiGatewayProxyResponse, size: usize) -> Result<ApiGatewayProxyResponse, HandlerError> {
+        if let Some(hook) = &self.response_overflow_hook {
+            let replacement = match hook(oversized).and_then(|response| self.gateway_response(accept, accept_encoding, response)) {
+                Ok(response) => response,
+                Err(e) => self.error_response(e)?,
+            };
+            if serde_json::to_vec(&replacement)?.len() <= self.response_size_limit {
+                return Ok(replacement);
+            }
+        }
+        let message = format!("response of {} bytes exceeds the {} byte limit", size, self.response_size_limit);
+        let payload = serde_json::to_string(&SrvrlsResponse::simple_error(message))?;
+        let mut headers = (self.response_header_interceptor)(HashMap::new());
+        if header_value(&headers, "Content-Type").is_none() {
+            headers.insert("Content-Type".to_string(), "application/json".to_string());
+        }
+        let mut gateway_response = Srvrls::<T>::response(500, Some(payload), headers);
+        gateway_response.multi_value_headers = (self.response_multi_value_header_interceptor)(HashMap::new());
+        Ok(gateway_response)
+    }
+
+    fn error_response(&self, error: SrvrlsError) -> Result<ApiGatewayProxyResponse, HandlerError> {
+        let (status_code, body) = error_status_and_body(error)?;
+        let mut gateway_response = Srvrls::<T>::response(status_code, body, (self.response_header_interceptor)(HashMap::new()));
+        gateway_response.multi_value_headers = (self.response_multi_value_header_interceptor)(HashMap::new());
+        Ok(gateway_response)
+    }
+
     fn response(status_code: i64, body: Option<String>, headers: HashMap<String, String>) -> ApiGatewayProxyResponse {
         ApiGatewayProxyResponse {
             status_code,
@@ -166,3 +533,28 @@ impl<T: SrvrlsApplication> Srvrls<T> {
         }
     }
 }
+
+pub(crate) fn error_status_and_body(error: SrvrlsError) -> Result<(i64, Option<String>), serde_json::Error> {
+    Ok(match error {
+        SrvrlsError::BadRequest(body) => (400, Some(body)),
+        SrvrlsError::BadRequestNoMessage() => (400, None),
+        SrvrlsError::BadRequestWithSimpleMessage(simple_message) => {
+            (400, Some(serde_json::to_string(&SrvrlsResponse::simple_error(simple_message))?))
+        }
+        SrvrlsError::Unauthorized => (401, None),
+        SrvrlsError::Forbidden => (403, None),
+        SrvrlsError::NotFound => (404, None),
+        SrvrlsError::MethodNotAllowed => (405, None),
+        SrvrlsError::NotAcceptable => (406, None),
+        SrvrlsError::PayloadTooLarge => (413, None),
+        SrvrlsError::InternalServerError => (500, None),
+    })
+}
+
+fn header_name(headers: &HashMap<String, String>, name: &str) -> String {
+    headers.keys().find(|key| key.eq_ignore_ascii_case(name)).cloned().unwrap_or_else(|| name.to_string())
+}
+
+fn header_value(headers: &HashMap<String, String>, name: &str) -> Option<String> {
+    headers.get(&header_name(headers, name)).cloned()
+}
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::apigw::{ApiGatewayCustomAuthorizerPolicy, ApiGatewayCustomAuthorizerResponse, IamPolicyStatement};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::error;
+use serde_json::Value;
+
+use crate::components::SrvrlsError;
+use crate::events::{AuthorizerRequest, HttpApiAuthorizerRequest, HttpApiSimpleAuthorizerResponse, http_api_proxy_request, proxy_request, ProxyRequestParts};
+use crate::request::SrvrlsRequest;
+
+/// The type of a Lambda authorizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorizerType {
+    /// A `TOKEN` authorizer, only the token is provided.
+    Token,
+    /// A `REQUEST` authorizer, the headers, query and path parameters of the request are provided.
+    Request,
+}
+
+/// The request to be authorized.
+pub struct AuthorizationRequest {
+    /// The type of the authorizer.
+    pub authorizer_type: AuthorizerType,
+    /// The token for `TOKEN` authorizers, the value of the identity source header (see
+    /// `SrvrlsAuthorizer::with_identity_source`) for `REQUEST` authorizers, or the first identity
+    /// source for HTTP API authorizers.
+    pub token: String,
+    /// The ARN of the method (or route) being invoked.
+    pub method_arn: String,
+    /// The request being authorized, this is empty for `TOKEN` authorizers.
+    pub request: SrvrlsRequest,
+}
+
+/// Whether a request is allowed or denied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// The request is allowed.
+    Allow,
+    /// The request is denied, API Gateway responds with a 403 - Forbidden.
+    Deny,
+}
+
+/// The result of authorizing a request.
+/// ```rust
+/// # use srvrls::authorizer::Authorization;
+/// let authorization = Authorization::allow("user|a1b2c3")
+///     .with_context("tenant", "acme")
+///     .with_context("admin", false);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Authorization {
+    /// Whether the request is allowed.
+    pub effect: Effect,
+    /// The principal id of the caller, available to the integration as `$context.authorizer.principalId`.
+    pub principal_id: String,
+    /// Values made available to the integration as `$context.authorizer.*`, API Gateway only
+    /// accepts string, number and boolean values.
+    pub context: HashMap<String, Value>,
+}
+
+impl Authorization {
+    /// Allows the request for the principal.
+    #[must_use]
+    pub fn allow(principal_id: &str) -> Self {
+        Authorization { effect: Effect::Allow, principal_id: principal_id.to_string(), context: HashMap::new() }
+    }
+
+    /// Denies the request for the principal.
+    #[must_use]
+    pub fn deny(principal_id: &str) -> Self {
+        Authorization { effect: Effect::Deny, principal_id: principal_id.to_string(), context: HashMap::new() }
+    }
+
+    /// Adds a value to the authorizer context.
+    #[must_use]
+    pub fn with_context<V: Into<Value>>(mut self, key: &str, value: V) -> Self {
+        self.context.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// This trait should be implemented by your Lambda authorizer.
+/// ```rust
+/// # use srvrls::authorizer::{Authorization, AuthorizationRequest, SrvrlsAuthorizerApplication};
+/// # use srvrls::components::SrvrlsError;
+/// struct TokenAuthorizer {}
+///
+/// impl SrvrlsAuthorizerApplication for TokenAuthorizer {
+///     fn authorize(&mut self, request: AuthorizationRequest) -> Result<Authorization, SrvrlsError> {
+///         match request.token.as_str() {
+///             "" => Err(SrvrlsError::Unauthorized),
+///             "Bearer allow" => Ok(Authorization::allow("user|a1b2c3").with_context("tenant", "acme")),
+///             _ => Ok(Authorization::deny("anonymous")),
+///         }
+///     }
+/// }
+/// ```
+pub trait SrvrlsAuthorizerApplication {
+    /// This method receives the request to be authorized and returns whether it is allowed.
+    ///
+    /// # Errors
+    /// A `SrvrlsError::Unauthorized` error results in a 401 - Unauthorized response (or a 403 for
+    /// HTTP API simple responses), any other error results in a 500 - Internal Server Error.
+    fn authorize(&mut self, request: AuthorizationRequest) -> Result<Authorization, SrvrlsError>;
+}
+
+/// `SrvrlsAuthorizer` wraps your application that implements `SrvrlsAuthorizerApplication` and
+/// interfaces with AWS Lambda for API Gateway Lambda authorizers.
+///
+/// `TOKEN` and `REQUEST` authorizers (and HTTP API payload format version 1.0) receive an IAM
+/// policy for the full stage of the API, e.g. `arn:aws:execute-api:{region}:{account}:{api}/{stage}/*/*`,
+/// so that a cached result applies to every method. See `SrvrlsHttpApiAuthorizer` for HTTP API
+/// payload format version 2.0.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let authorizer = SrvrlsAuthorizer::new(TokenAuthorizer {});
+///     lambda!(authorizer);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsAuthorizer<T: SrvrlsAuthorizerApplication> {
+    application: T,
+    identity_source: String,
+}
+
+impl<T: SrvrlsAuthorizerApplication> SrvrlsAuthorizer<T> {
+    /// Create a new `SrvrlsAuthorizer` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsAuthorizer { application, identity_source: "Authorization".to_string() }
+    }
+
+    /// Sets the header the token of a `REQUEST` authorizer is taken from, defaults to
+    /// `Authorization`. API Gateway does not provide the token for `REQUEST` authorizers, this
+    /// should match the header configured as the identity source of the authorizer.
+    /// ```rust
+    /// # use srvrls::authorizer::{Authorization, AuthorizationRequest, SrvrlsAuthorizer, SrvrlsAuthorizerApplication};
+    /// # use srvrls::components::SrvrlsError;
+    /// # struct ApiKeyAuthorizer {}
+    /// # impl SrvrlsAuthorizerApplication for ApiKeyAuthorizer {
+    /// #     fn authorize(&mut self, request: AuthorizationRequest) -> Result<Authorization, SrvrlsError> {
+    /// #         Ok(Authorization::allow(&request.token))
+    /// #     }
+    /// # }
+    /// let mut authorizer = SrvrlsAuthorizer::new(ApiKeyAuthorizer {});
+    /// authorizer.with_identity_source("X-Api-Key");
+    /// ```
+    pub fn with_identity_source(&mut self, header: &str) {
+        self.identity_source = header.to_string();
+    }
+}
+
+impl<T: SrvrlsAuthorizerApplication> Handler<AuthorizerRequest, ApiGatewayCustomAuthorizerResponse, HandlerError> for SrvrlsAuthorizer<T> {
+    fn run(&mut self, event: AuthorizerRequest, _ctx: Context) -> Result<ApiGatewayCustomAuthorizerResponse, HandlerError> {
+        let method_arn = event.method_arn.unwrap_or_default();
+        let authorizer_type = match event.authorizer_type.as_deref() {
+            Some("REQUEST") => AuthorizerType::Request,
+            _ => AuthorizerType::Token,
+        };
+        let mut multi_value_query_string_parameters = event.multi_value_query_string_parameters;
+        for (k, v) in event.query_string_parameters {
+            multi_value_query_string_parameters.entry(k).or_insert_with(|| vec![v]);
+        }
+        let mut request: SrvrlsRequest = proxy_request(ProxyRequestParts {
+            path: event.path.unwrap_or_default(),
+            http_method: event.http_method,
+            headers: event.headers,
+            multi_value_headers: event.multi_value_headers,
+            multi_value_query_string_parameters,
+            authorizer: HashMap::new(),
+            body: None,
+            is_base64_encoded: false,
+        }).into();
+        request.path_parameters.extend(event.path_parameters);
+        let token = match authorizer_type {
+            AuthorizerType::Token => event.authorization_token.unwrap_or_default(),
+            AuthorizerType::Request => request.header(&self.identity_source),
+        };
+        let authorization = self.application.authorize(AuthorizationRequest {
+            authorizer_type,
+            token,
+            method_arn: method_arn.clone(),
+            request,
+        }).map_err(authorizer_error)?;
+        let effect = match authorization.effect {
+            Effect::Allow => "Allow",
+            Effect::Deny => "Deny",
+        };
+        Ok(ApiGatewayCustomAuthorizerResponse {
+            principal_id: Some(authorization.principal_id),
+            policy_document: ApiGatewayCustomAuthorizerPolicy {
+                version: Some("2012-10-17".to_string()),
+                statement: vec![IamPolicyStatement {
+                    action: vec!["execute-api:Invoke".to_string()],
+                    effect: Some(effect.to_string()),
+                    resource: vec![stage_arn(&method_arn)],
+                }],
+            },
+            context: authorization.context,
+            usage_identifier_key: None,
+        })
+    }
+}
+
+/// `SrvrlsHttpApiAuthorizer` wraps your application that implements `SrvrlsAuthorizerApplication`
+/// and interfaces with AWS Lambda for API Gateway HTTP API Lambda authorizers using payload format
+/// version 2.0, returning the simple response format. The token is the first identity source.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let authorizer = SrvrlsHttpApiAuthorizer::new(TokenAuthorizer {});
+///     lambda!(authorizer);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsHttpApiAuthorizer<T: SrvrlsAuthorizerApplication> {
+    application: T,
+}
+
+impl<T: SrvrlsAuthorizerApplication> SrvrlsHttpApiAuthorizer<T> {
+    /// Create a new `SrvrlsHttpApiAuthorizer` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsHttpApiAuthorizer { application }
+    }
+}
+
+impl<T: SrvrlsAuthorizerApplication> Handler<HttpApiAuthorizerRequest, HttpApiSimpleAuthorizerResponse, HandlerError> for SrvrlsHttpApiAuthorizer<T> {
+    fn run(&mut self, event: HttpApiAuthorizerRequest, _ctx: Context) -> Result<HttpApiSimpleAuthorizerResponse, HandlerError> {
+        let authorization = self.application.authorize(AuthorizationRequest {
+            authorizer_type: AuthorizerType::Request,
+            token: event.identity_source.first().cloned().unwrap_or_default(),
+            method_arn: event.route_arn.unwrap_or_default(),
+            request: http_api_proxy_request(event.request).into(),
+        });
+        match authorization {
+            Ok(authorization) => Ok(HttpApiSimpleAuthorizerResponse {
+                is_authorized: authorization.effect == Effect::Allow,
+                context: authorization.context,
+            }),
+            Err(SrvrlsError::Unauthorized) => Ok(HttpApiSimpleAuthorizerResponse::default()),
+            Err(e) => Err(authorizer_error(e)),
+        }
+    }
+}
+
+/// API Gateway responds with a 401 - Unauthorized only for this exact error message.
+fn authorizer_error(error: SrvrlsError) -> HandlerError {
+    match error {
+        SrvrlsError::Unauthorized => HandlerError::from("Unauthorized"),
+        e => {
+            error!("authorizer failed: {}", e);
+            HandlerError::from(e.to_string().as_str())
+        }
+    }
+}
+
+/// Widens a method ARN to every method and path of its' stage.
+fn stage_arn(method_arn: &str) -> String {
+    let mut parts = method_arn.splitn(3, '/');
+    match (parts.next(), parts.next()) {
+        (Some(api), Some(stage)) => format!("{}/{}/*/*", api, stage),
+        _ => method_arn.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod authorizer_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    struct TenantAuthorizer {}
+
+    impl SrvrlsAuthorizerApplication for TenantAuthorizer {
+        fn authorize(&mut self, request: AuthorizationRequest) -> Result<Authorization, SrvrlsError> {
+            let tenant = match request.authorizer_type {
+                AuthorizerType::Token => "token".to_string(),
+                AuthorizerType::Request => format!("{}:{}", request.request.header("X-Tenant"), request.request.path_param::<String, _>("id").unwrap_or_default()),
+            };
+            match request.token.as_str() {
+                "" => Err(SrvrlsError::Unauthorized),
+                "Bearer allow" => Ok(Authorization::allow("user|a1b2c3").with_context("tenant", tenant).with_context("admin", false)),
+                "Bearer broken" => Err(SrvrlsError::InternalServerError),
+                _ => Ok(Authorization::deny("anonymous")),
+            }
+        }
+    }
+
+    const METHOD_ARN: &str = "arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/customer/CUST-A23948";
+
+    fn token_event(token: &str) -> AuthorizerRequest {
+        serde_json::from_value(json!({
+            "type": "TOKEN",
+            "authorizationToken": token,
+            "methodArn": METHOD_ARN
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_token_policy() {
+        let mut authorizer = SrvrlsAuthorizer::new(TenantAuthorizer {});
+        let response: ApiGatewayCustomAuthorizerResponse = authorizer.run(token_event("Bearer allow"), Context::default()).unwrap();
+        assert_eq!(json!({
+            "principalId": "user|a1b2c3",
+            "policyDocument": {
+                "Version": "2012-10-17",
+                "Statement": [{
+                    "Action": ["execute-api:Invoke"],
+                    "Effect": "Allow",
+                    "Resource": ["arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/*/*"]
+                }]
+            },
+            "context": {"tenant": "token", "admin": false},
+            "usageIdentifierKey": null
+        }), serde_json::to_value(response).unwrap());
+
+        let response: ApiGatewayCustomAuthorizerResponse = authorizer.run(token_event("Bearer deny"), Context::default()).unwrap();
+        assert_eq!(Some("Deny".to_string()), response.policy_document.statement[0].effect);
+
+        let result: Result<ApiGatewayCustomAuthorizerResponse, HandlerError> = authorizer.run(token_event(""), Context::default());
+        assert!(format!("{:?}", result.unwrap_err()).contains("Unauthorized"));
+    }
+
+    #[test]
+    fn test_request_authorizer() {
+        let event = |headers: Value| -> AuthorizerRequest {
+            serde_json::from_value(json!({
+                "type": "REQUEST",
+                "methodArn": METHOD_ARN,
+                "resource": "/customer/{id}",
+                "path": "/customer/CUST-A23948",
+                "httpMethod": "GET",
+                "headers": headers,
+                "queryStringParameters": null,
+                "pathParameters": {"id": "CUST-A23948"},
+                "requestContext": {"stage": "prod"}
+            })).unwrap()
+        };
+        let mut authorizer = SrvrlsAuthorizer::new(TenantAuthorizer {});
+        let response: ApiGatewayCustomAuthorizerResponse = authorizer.run(event(json!({"authorization": "Bearer allow", "X-Tenant": "acme"})), Context::default()).unwrap();
+        assert_eq!(Some(&json!("acme:CUST-A23948")), response.context.get("tenant"));
+
+        let result: Result<ApiGatewayCustomAuthorizerResponse, HandlerError> = authorizer.run(event(json!({"X-Tenant": "acme"})), Context::default());
+        assert!(format!("{:?}", result.unwrap_err()).contains("Unauthorized"));
+
+        authorizer.with_identity_source("X-Api-Key");
+        let response: ApiGatewayCustomAuthorizerResponse = authorizer.run(event(json!({"Authorization": "Bearer deny", "X-Api-Key": "Bearer allow"})), Context::default()).unwrap();
+        assert_eq!(Some("Allow".to_string()), response.policy_document.statement[0].effect);
+    }
+
+    #[test]
+    fn test_http_api_simple_response() {
+        let event = |identity_source: &str| -> HttpApiAuthorizerRequest {
+            serde_json::from_value(json!({
+                "version": "2.0",
+                "type": "REQUEST",
+                "routeArn": METHOD_ARN,
+                "identitySource": [identity_source],
+                "routeKey": "GET /customer/{id}",
+                "rawPath": "/customer/CUST-A23948",
+                "pathParameters": {"id": "CUST-A23948"},
+                "headers": {"authorization": identity_source, "x-tenant": "acme"},
+                "requestContext": {"http": {"method": "GET", "path": "/customer/CUST-A23948"}}
+            })).unwrap()
+        };
+        let mut authorizer = SrvrlsHttpApiAuthorizer::new(TenantAuthorizer {});
+        let response: HttpApiSimpleAuthorizerResponse = authorizer.run(event("Bearer allow"), Context::default()).unwrap();
+        assert_eq!(json!({"isAuthorized": true, "context": {"tenant": "acme:CUST-A23948", "admin": false}}), serde_json::to_value(response).unwrap());
+
+        let response: HttpApiSimpleAuthorizerResponse = authorizer.run(event("Bearer deny"), Context::default()).unwrap();
+        assert!(!response.is_authorized);
+        let response: HttpApiSimpleAuthorizerResponse = authorizer.run(event(""), Context::default()).unwrap();
+        assert!(!response.is_authorized);
+        let result: Result<HttpApiSimpleAuthorizerResponse, HandlerError> = authorizer.run(event("Bearer broken"), Context::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stage_arn() {
+        assert_eq!("arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/*/*", stage_arn(METHOD_ARN));
+        assert_eq!("arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/*/*", stage_arn("arn:aws:execute-api:us-east-1:123456789012:abcdef123/prod/GET/"));
+        assert_eq!("not-an-arn", stage_arn("not-an-arn"));
+    }
+}
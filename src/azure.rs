@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use aws_lambda_events::event::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse};
+use lambda_runtime::{Context, Handler};
+use log::error;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::application::{Srvrls, SrvrlsApplication};
+use crate::components::parse_url_encoded;
+use crate::events::{null_as_default, proxy_request, ProxyRequestParts};
+
+/// The HTTP trigger request as sent by the Azure Functions host in the custom handler invocation
+/// payload.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AzureHttpRequest {
+    /// The full request url, e.g. `https://example.azurewebsites.net/api/orders/1?expand=items`.
+    #[serde(default)]
+    pub url: String,
+    /// The http method.
+    #[serde(default)]
+    pub method: String,
+    /// The request headers, each with all of its' values.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub headers: HashMap<String, Vec<String>>,
+    /// The route parameters of the function's route template.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub params: HashMap<String, String>,
+    /// The request body.
+    #[serde(default)]
+    pub body: Value,
+}
+
+/// A single Easy Auth (App Service Authentication) claim.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+struct ClientPrincipalClaim {
+    #[serde(default)]
+    typ: String,
+    #[serde(default)]
+    val: String,
+}
+
+/// The Easy Auth principal, base64 encoded in the `X-MS-CLIENT-PRINCIPAL` header.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+struct ClientPrincipal {
+    #[serde(default)]
+    auth_typ: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    claims: Vec<ClientPrincipalClaim>,
+}
+
+/// `SrvrlsAzure` runs an unchanged `SrvrlsApplication` as an Azure Functions custom handler,
+/// serving the custom handler invocation protocol on the port provided by the Functions host.
+///
+/// Each invocation's HTTP trigger request is converted to a `SrvrlsRequest` with the route prefix
+/// (`api` by default) removed from the path and any Easy Auth principal claims, from the
+/// `X-MS-CLIENT-PRINCIPAL` header, available as authentication claims. The route parameters of the
+/// function's route template are available as path parameters. The response is rendered by
+/// the wrapped `Srvrls` instance, with all of its' configuration, and returned in the HTTP output
+/// binding. Headers with multiple values are comma separated, each `Set-Cookie` header is returned
+/// in the binding's `cookies` array instead, and binary bodies are returned as an array of bytes.
+/// ```rust
+/// # use srvrls::application::{Srvrls, SrvrlsApplication};
+/// # use srvrls::azure::SrvrlsAzure;
+/// # use srvrls::components::SrvrlsError;
+/// # use srvrls::request::SrvrlsRequest;
+/// # use srvrls::response::SrvrlsResponse;
+/// # struct App {}
+/// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+/// #         Ok(SrvrlsResponse::ok_empty())
+/// #     }
+/// # }
+/// let mut azure = SrvrlsAzure::new(Srvrls::new(App {}));
+/// azure.with_bindings("request", "response");
+/// ```
+/// This `SrvrlsAzure` object is then served from the `main` of the executable named as the
+/// `customHandler` in your `host.json`.
+/// ```ignore
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let mut azure = SrvrlsAzure::new(build_srvrls());
+///     azure.serve()?;
+///     Ok(())
+/// }
+/// ```
+/// Each HTTP triggered function should then use a catch-all route, e.g. `{*path}`, with
+/// `enableForwardingHttpRequest` disabled.
+pub struct SrvrlsAzure<T: SrvrlsApplication> {
+    srvrls: Srvrls<T>,
+    request_binding: String,
+    response_binding: String,
+    route_prefix: String,
+    read_timeout: Duration,
+}
+
+/// The largest invocation payload accepted, the Functions host limits HTTP requests to 100 MB.
+const MAX_CONTENT_LENGTH: usize = 100 * 1024 * 1024;
+
+/// The largest request line and headers accepted, together.
+const MAX_HEADER_SIZE: u64 = 64 * 1024;
+
+impl<T: SrvrlsApplication> SrvrlsAzure<T> {
+    /// Create a new `SrvrlsAzure` instance for the provided `Srvrls` instance, using the `req`
+    /// and `res` bindings, the `api` route prefix and a 30 second read timeout.
+    pub fn new(srvrls: Srvrls<T>) -> Self {
+        SrvrlsAzure {
+            srvrls,
+            request_binding: "req".to_string(),
+            response_binding: "res".to_string(),
+            route_prefix: "api".to_string(),
+            read_timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// Sets the names of the HTTP trigger and HTTP output bindings used in your `function.json`
+    /// files.
+    pub fn with_bindings(&mut self, request_binding: &str, response_binding: &str) {
+        self.request_binding = request_binding.to_string();
+        self.response_binding = response_binding.to_string();
+    }
+
+    /// Sets the route prefix configured in your `host.json`, an empty prefix may be used if the
+    /// route prefix has been removed.
+    pub fn with_route_prefix(&mut self, route_prefix: &str) {
+        self.route_prefix = route_prefix.trim_matches('/').to_string();
+    }
+
+    /// Sets how long to wait for an invocation request to be received, a connection that stalls for
+    /// longer is closed so that it does not block the invocations that follow.
+    pub fn with_read_timeout(&mut self, read_timeout: Duration) {
+        self.read_timeout = read_timeout;
+    }
+
+    /// Serves invocations on the port provided by the Functions host in the
+    /// `FUNCTIONS_CUSTOMHANDLER_PORT` environment variable, this only returns on an error.
+    ///
+    /// # Errors
+    /// Returns an error if the port is not provided or cannot be bound.
+    pub fn serve(&mut self) -> Result<(), Box<dyn Error>> {
+        let port = std::env::var("FUNCTIONS_CUSTOMHANDLER_PORT")?;
+        let listener = TcpListener::bind(format!("127.0.0.1:{}", port))?;
+        self.serve_on(&listener)?;
+        Ok(())
+    }
+
+    /// Serves invocations to connections accepted by the provided listener, this only returns on
+    /// an error accepting a connection. Failures within a single connection are logged.
+    ///
+    /// Connections are served one at a time on the calling thread, as the application is borrowed
+    /// mutably for each invocation, and are closed after the response. Invocations received
+    /// concurrently by the Functions host wait for the current invocation to complete, so
+    /// throughput is scaled with `FUNCTIONS_WORKER_PROCESS_COUNT` or further instances rather than
+    /// within the process. A request with a missing, empty or incomplete body, or a `Content-Length`
+    /// that is invalid or larger than 100 MB, is answered with a 400 - Bad Request. A request line and
+    /// headers larger than 64 KB are answered with a 431 - Request Header Fields Too Large.
+    ///
+    /// # Errors
+    /// Returns an error if a connection cannot be accepted.
+    pub fn serve_on(&mut self, listener: &TcpListener) -> std::io::Result<()> {
+        for stream in listener.incoming() {
+            if let Err(e) = self.handle_connection(stream?) {
+                error!("custom handler connection failed: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a single custom handler invocation payload through the application and returns the
+    /// invocation response, holding the HTTP response in its' `Outputs`.
+    ///
+    /// # Errors
+    /// Returns an error if the payload does not hold an HTTP trigger request.
+    pub fn invoke(&mut self, invocation: Value) -> Result<Value, Box<dyn Error>> {
+        let request = match invocation.get("Data").and_then(|data| data.get(&self.request_binding)) {
+            Some(request) => serde_json::from_value(request.clone())?,
+            None => return Err(format!("invocation has no '{}' binding", self.request_binding).into()),
+        };
+        let response: ApiGatewayProxyResponse = self.srvrls.run(self.proxy_request(request), Context::default())?;
+        let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+        let single_value_headers = response.headers.into_iter().map(|(k, v)| (k, vec![v]));
+        for (k, v) in single_value_headers.chain(response.multi_value_headers) {
+            headers.entry(k).or_default().extend(v);
+        }
+        let cookies: Vec<Value> = headers.iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("Set-Cookie"))
+            .flat_map(|(_, v)| v.iter().map(|set_cookie| azure_cookie(set_cookie)))
+            .collect();
+        let headers: HashMap<String, String> = headers.into_iter()
+            .filter(|(k, _)| !k.eq_ignore_ascii_case("Set-Cookie"))
+            .map(|(k, v)| (k, v.join(", ")))
+            .collect();
+        let body = match (response.body, response.is_base64_encoded.unwrap_or(false)) {
+            (Some(body), true) => json!(base64::decode(body)?),
+            (Some(body), false) => json!(body),
+            (None, _) => Value::Null,
+        };
+        let mut outputs = Map::new();
+        outputs.insert(self.response_binding.clone(), json!({
+            "statusCode": response.status_code,
+            "headers": headers,
+            "cookies": cookies,
+            "body": body,
+        }));
+        Ok(json!({"Outputs": outputs, "Logs": [], "ReturnValue": null}))
+    }
+
+    fn proxy_request(&self, request: AzureHttpRequest) -> ApiGatewayProxyRequest {
+        let without_scheme = request.url.splitn(2, "://").last().unwrap_or_default();
+        let path_and_query = without_scheme.find('/').map_or("", |start| &without_scheme[start..]);
+        let (path, query) = match path_and_query.find('?') {
+            Some(position) => (&path_and_query[..position], &path_and_query[position + 1..]),
+            None => (path_and_query, ""),
+        };
+        let path = path.trim_start_matches('/');
+        let path = match path.strip_prefix(&self.route_prefix) {
+            Some(rest) if !self.route_prefix.is_empty() && (rest.is_empty() || rest.starts_with('/')) => rest,
+            _ => path,
+        };
+        let mut authorizer = HashMap::new();
+        let principal = request.headers.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("X-MS-CLIENT-PRINCIPAL"))
+            .and_then(|(_, v)| v.first())
+            .and_then(|principal| client_principal(principal));
+        if let Some(principal) = principal {
+            let mut claims: Map<String, Value> = Map::new();
+            claims.insert("auth_typ".to_string(), json!(principal.auth_typ));
+            for claim in principal.claims {
+                let value = match claims.get(&claim.typ).and_then(Value::as_str) {
+                    Some(existing) => format!("{},{}", existing, claim.val),
+                    None => claim.val,
+                };
+                claims.insert(claim.typ, json!(value));
+            }
+            authorizer.insert("claims".to_string(), Value::Object(claims));
+        }
+        let body = match request.body {
+            Value::Null => None,
+            Value::String(body) => Some(body),
+            body => Some(body.to_string()),
+        };
+        let mut proxy_request = proxy_request(ProxyRequestParts {
+            path: format!("/{}", path.trim_start_matches('/')),
+            http_method: Some(request.method),
+            headers: HashMap::new(),
+            multi_value_headers: request.headers,
+            multi_value_query_string_parameters: parse_url_encoded(query),
+            authorizer,
+            body,
+            is_base64_encoded: false,
+        });
+        proxy_request.path_parameters.extend(request.params);
+        proxy_request
+    }
+
+    fn handle_connection(&mut self, mut stream: TcpStream) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(self.read_timeout))?;
+        stream.set_write_timeout(Some(self.read_timeout))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut head = reader.by_ref().take(MAX_HEADER_SIZE);
+        let mut request_line = String::new();
+        head.read_line(&mut request_line)?;
+        let mut content_length = Err(("400 Bad Request", "missing Content-Length".to_string()));
+        loop {
+            let mut line = String::new();
+            if head.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("Content-Length") {
+                    content_length = match value.trim().parse() {
+                        Ok(0) => Err(("400 Bad Request", "empty invocation body".to_string())),
+                        Ok(length) if length <= MAX_CONTENT_LENGTH => Ok(length),
+                        Ok(length) => Err(("400 Bad Request", format!("Content-Length of {} bytes exceeds the {} byte limit", length, MAX_CONTENT_LENGTH))),
+                        Err(_) => Err(("400 Bad Request", format!("invalid Content-Length '{}'", value.trim()))),
+                    };
+                }
+            }
+        }
+        if head.limit() == 0 {
+            content_length = Err(("431 Request Header Fields Too Large", format!("request headers exceed the {} byte limit", MAX_HEADER_SIZE)));
+        }
+        let mut body = vec![0; content_length.as_ref().map_or(0, |length| *length)];
+        let read_body = content_length.and_then(|_| reader.read_exact(&mut body).map_err(|e| ("400 Bad Request", e.to_string())));
+        let (status, payload) = match read_body {
+            Err((status, e)) => {
+                error!("invocation {} rejected: {}", request_line.trim(), e);
+                (status, json!({"Outputs": {}, "Logs": [e], "ReturnValue": null}))
+            }
+            Ok(()) => match serde_json::from_slice(&body).map_err(Box::from).and_then(|invocation| self.invoke(invocation)) {
+                Ok(payload) => ("200 OK", payload),
+                Err(e) => {
+                    error!("invocation {} failed: {}", request_line.trim(), e);
+                    ("500 Internal Server Error", json!({"Outputs": {}, "Logs": [e.to_string()], "ReturnValue": null}))
+                }
+            },
+        };
+        let payload = payload.to_string();
+        write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+               status, payload.len(), payload)?;
+        stream.flush()
+    }
+}
+
+/// Converts a `Set-Cookie` header value into a cookie of the HTTP output binding.
+fn azure_cookie(set_cookie: &str) -> Value {
+    let mut attributes = set_cookie.split(';');
+    let (name, value) = attributes.next().unwrap_or_default().split_once('=').unwrap_or((set_cookie, ""));
+    let mut cookie = json!({"name": name.trim(), "value": value.trim()});
+    for attribute in attributes {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "domain" => cookie["domain"] = json!(value),
+            "path" => cookie["path"] = json!(value),
+            "expires" => cookie["expires"] = json!(value),
+            "max-age" => cookie["maxAge"] = value.parse::<i64>().map_or(Value::Null, |max_age| json!(max_age)),
+            "samesite" => cookie["sameSite"] = json!(value),
+            "secure" => cookie["secure"] = json!(true),
+            "httponly" => cookie["httpOnly"] = json!(true),
+            _ => {}
+        }
+    }
+    cookie
+}
+
+fn client_principal(header: &str) -> Option<ClientPrincipal> {
+    let decoded = base64::decode(header.trim()).ok()?;
+    serde_json::from_slice(&decoded).ok()
+}
+
+#[cfg(test)]
+mod azure_tests {
+    use crate::components::SrvrlsError;
+    use crate::cookie::{Cookie, SameSite};
+    use crate::request::SrvrlsRequest;
+    use crate::response::SrvrlsResponse;
+
+    use super::*;
+
+    struct EchoApplication {}
+
+    impl SrvrlsApplication for EchoApplication {
+        fn handle(&mut self, event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+            match event.path_parameter(0).as_str() {
+                "orders" => {
+                    let mut response = SrvrlsResponse::ok(json!({
+                        "method": format!("{:?}", event.method),
+                        "path": event.path,
+                        "expand": event.query_parameter("expand"),
+                        "accept": event.header("Accept"),
+                        "name": event.authentication_claim("name"),
+                        "roles": event.authentication_claim("roles"),
+                        "route": event.path_param::<String, _>("path").unwrap_or_default(),
+                        "body": event.body,
+                    }));
+                    response.add_cookie(Cookie::new("session", "abc"));
+                    response.add_cookie(Cookie::removal("cart").path("/").secure().http_only().same_site(SameSite::Strict));
+                    Ok(response)
+                }
+                _ => Err(SrvrlsError::NotFound),
+            }
+        }
+    }
+
+    fn invocation(url: &str, headers: Value) -> Value {
+        json!({
+            "Data": {
+                "req": {
+                    "Url": url,
+                    "Method": "POST",
+                    "Query": {},
+                    "Headers": headers,
+                    "Params": {"path": "orders/1"},
+                    "Body": "{\"quantity\":2}"
+                }
+            },
+            "Metadata": {"sys": {"MethodName": "orders"}}
+        })
+    }
+
+    #[test]
+    fn test_invoke() {
+        let principal = base64::encode(json!({
+            "auth_typ": "aad",
+            "claims": [
+                {"typ": "name", "val": "Jane Doe"},
+                {"typ": "roles", "val": "reader"},
+                {"typ": "roles", "val": "writer"}
+            ],
+            "name_typ": "name",
+            "role_typ": "roles"
+        }).to_string());
+        let headers = json!({"Accept": ["application/json"], "X-MS-CLIENT-PRINCIPAL": [principal]});
+        let mut azure = SrvrlsAzure::new(Srvrls::new(EchoApplication {}));
+        let response = azure.invoke(invocation("https://example.azurewebsites.net/api/orders/1?expand=items&expand=notes", headers)).unwrap();
+
+        let res = &response["Outputs"]["res"];
+        assert_eq!(200, res["statusCode"]);
+        assert_eq!("application/json", res["headers"]["Content-Type"]);
+        assert_eq!(None, res["headers"].get("Set-Cookie"));
+        assert_eq!(json!([
+            {"name": "session", "value": "abc"},
+            {"name": "cart", "value": "", "maxAge": 0, "path": "/", "secure": true, "httpOnly": true, "sameSite": "Strict"}
+        ]), res["cookies"]);
+        let body: Value = serde_json::from_str(res["body"].as_str().unwrap()).unwrap();
+        assert_eq!(json!({
+            "method": "POST",
+            "path": "orders/1",
+            "expand": ["items", "notes"],
+            "accept": "application/json",
+            "name": "Jane Doe",
+            "roles": "reader,writer",
+            "route": "orders/1",
+            "body": "{\"quantity\":2}"
+        }), body);
+    }
+
+    #[test]
+    fn test_bindings_and_route_prefix() {
+        let mut azure = SrvrlsAzure::new(Srvrls::new(EchoApplication {}));
+        azure.with_route_prefix("");
+        let response = azure.invoke(invocation("http://localhost:7071/api/orders/1", json!({}))).unwrap();
+        assert_eq!(404, response["Outputs"]["res"]["statusCode"]);
+
+        azure.with_route_prefix("/api/");
+        azure.with_bindings("request", "response");
+        assert!(azure.invoke(invocation("http://localhost:7071/api/orders/1", json!({}))).is_err());
+        let mut invocation = invocation("http://localhost:7071/api/orders/1", json!({}));
+        invocation["Data"]["request"] = invocation["Data"]["req"].take();
+        let response = azure.invoke(invocation).unwrap();
+        assert_eq!(200, response["Outputs"]["response"]["statusCode"]);
+        let body: Value = serde_json::from_str(response["Outputs"]["response"]["body"].as_str().unwrap()).unwrap();
+        assert_eq!("", body["name"]);
+    }
+
+    fn send(address: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_serve_on() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let mut azure = SrvrlsAzure::new(Srvrls::new(EchoApplication {}));
+            azure.with_read_timeout(Duration::from_millis(200));
+            azure.serve_on(&listener).unwrap();
+        });
+        let stalled = TcpStream::connect(address).unwrap();
+        let payload = invocation("http://localhost:7071/api/orders/1", json!({})).to_string();
+        let response = send(address, &format!("POST /orders HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                                               address, payload.len(), payload));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let body: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(200, body["Outputs"]["res"]["statusCode"]);
+        drop(stalled);
+
+        let response = send(address, "POST /orders HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let response = send(address, "POST /orders HTTP/1.1\r\nContent-Length: 0\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let response = send(address, "POST /orders HTTP/1.1\r\nContent-Length: -1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let response = send(address, "POST /orders HTTP/1.1\r\nContent-Length: 104857601\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let response = send(address, "POST /orders HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        let mut oversized = "POST /orders HTTP/1.1\r\nX-Padding: ".to_string();
+        oversized.push_str(&"a".repeat(MAX_HEADER_SIZE as usize - oversized.len()));
+        let response = send(address, &oversized);
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    }
+}
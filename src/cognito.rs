@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::components::SrvrlsError;
+use crate::events::null_as_default;
+
+/// The details common to all Cognito user pool triggers.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CognitoTrigger {
+    /// The trigger source, e.g. `PreSignUp_SignUp` or `TokenGeneration_RefreshTokens`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub trigger_source: String,
+    /// The id of the user pool.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub user_pool_id: String,
+    /// The user name of the user.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub user_name: String,
+    /// The AWS region of the user pool.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub region: String,
+    /// The caller of the user pool.
+    #[serde(default)]
+    pub caller_context: CognitoCallerContext,
+}
+
+/// The caller of a Cognito user pool trigger.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CognitoCallerContext {
+    /// The version of the AWS SDK used by the caller.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub aws_sdk_version: String,
+    /// The id of the app client.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub client_id: String,
+}
+
+/// The request of the pre sign-up trigger.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreSignUpRequest {
+    /// The attributes the user signed up with.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub user_attributes: HashMap<String, String>,
+    /// The validation data sent with the sign up.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub validation_data: HashMap<String, String>,
+    /// The client metadata sent with the sign up.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub client_metadata: HashMap<String, String>,
+}
+
+/// The response of the pre sign-up trigger, by default the user must confirm their' account.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreSignUpResponse {
+    /// Confirms the user without a confirmation code.
+    pub auto_confirm_user: bool,
+    /// Marks the email address as verified.
+    pub auto_verify_email: bool,
+    /// Marks the phone number as verified.
+    pub auto_verify_phone: bool,
+}
+
+/// The request of the post confirmation trigger.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostConfirmationRequest {
+    /// The attributes of the confirmed user.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub user_attributes: HashMap<String, String>,
+    /// The client metadata sent with the confirmation.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub client_metadata: HashMap<String, String>,
+}
+
+/// The groups and IAM roles of a user, used in the pre token generation trigger.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupConfiguration {
+    /// The groups of the user.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub groups_to_override: Vec<String>,
+    /// The IAM roles of the user's groups.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub iam_roles_to_override: Vec<String>,
+    /// The preferred IAM role.
+    #[serde(default)]
+    pub preferred_role: Option<String>,
+}
+
+/// The request of the pre token generation trigger.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreTokenGenerationRequest {
+    /// The attributes of the user.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub user_attributes: HashMap<String, String>,
+    /// The current groups and IAM roles of the user.
+    #[serde(default)]
+    pub group_configuration: GroupConfiguration,
+    /// The client metadata sent with the authentication.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub client_metadata: HashMap<String, String>,
+}
+
+/// The response of the pre token generation trigger, by default the token is unchanged.
+/// ```rust
+/// # use srvrls::cognito::PreTokenGenerationResponse;
+/// let response = PreTokenGenerationResponse::default()
+///     .add_claim("tenant", "acme")
+///     .suppress_claim("phone_number");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PreTokenGenerationResponse {
+    /// The claims to add to the token, replacing any existing value.
+    pub claims_to_add_or_override: HashMap<String, String>,
+    /// The claims to remove from the token.
+    pub claims_to_suppress: Vec<String>,
+    /// Replaces the groups and IAM roles in the token.
+    pub group_override_details: Option<GroupConfiguration>,
+}
+
+impl PreTokenGenerationResponse {
+    /// Adds a claim to the token, replacing any existing value.
+    #[must_use]
+    pub fn add_claim(mut self, claim: &str, value: &str) -> Self {
+        self.claims_to_add_or_override.insert(claim.to_string(), value.to_string());
+        self
+    }
+
+    /// Removes a claim from the token.
+    #[must_use]
+    pub fn suppress_claim(mut self, claim: &str) -> Self {
+        self.claims_to_suppress.push(claim.to_string());
+        self
+    }
+
+    /// Replaces the groups and IAM roles in the token.
+    #[must_use]
+    pub fn override_groups(mut self, group_configuration: GroupConfiguration) -> Self {
+        self.group_override_details = Some(group_configuration);
+        self
+    }
+
+    fn to_value(&self) -> Value {
+        let mut details = json!({
+            "claimsToAddOrOverride": self.claims_to_add_or_override,
+            "claimsToSuppress": self.claims_to_suppress,
+        });
+        if let Some(group_override_details) = &self.group_override_details {
+            details["groupOverrideDetails"] = json!(group_override_details);
+        }
+        json!({ "claimsOverrideDetails": details })
+    }
+}
+
+/// The request of the custom message trigger.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomMessageRequest {
+    /// The attributes of the user.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub user_attributes: HashMap<String, String>,
+    /// The placeholder that must appear in the message where the code is inserted, e.g. `{####}`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub code_parameter: String,
+    /// The placeholder for the user name, only used for `CustomMessage_AdminCreateUser`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub username_parameter: String,
+    /// The client metadata sent with the request.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub client_metadata: HashMap<String, String>,
+}
+
+/// The response of the custom message trigger, any message that is `None` uses the user pool's
+/// default message.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomMessageResponse {
+    /// The SMS message, this must contain the code parameter.
+    pub sms_message: Option<String>,
+    /// The email message, this must contain the code parameter.
+    pub email_message: Option<String>,
+    /// The email subject.
+    pub email_subject: Option<String>,
+}
+
+/// This trait should be implemented by your application to handle Cognito user pool triggers, each
+/// trigger has a default that leaves the user pool's behaviour unchanged so only the triggers in
+/// use need to be implemented.
+/// ```rust
+/// # use srvrls::cognito::{CognitoTrigger, PreSignUpRequest, PreSignUpResponse, PreTokenGenerationRequest, PreTokenGenerationResponse, SrvrlsCognitoApplication};
+/// # use srvrls::components::SrvrlsError;
+/// struct UserPoolApplication {}
+///
+/// impl SrvrlsCognitoApplication for UserPoolApplication {
+///     fn pre_sign_up(&mut self, _trigger: &CognitoTrigger, request: PreSignUpRequest) -> Result<PreSignUpResponse, SrvrlsError> {
+///         match request.user_attributes.get("email") {
+///             Some(email) if email.ends_with("@example.com") => Ok(PreSignUpResponse { auto_confirm_user: true, ..Default::default() }),
+///             _ => Err(SrvrlsError::BadRequest("sign up is restricted to example.com".to_string())),
+///         }
+///     }
+///
+///     fn pre_token_generation(&mut self, _trigger: &CognitoTrigger, _request: PreTokenGenerationRequest) -> Result<PreTokenGenerationResponse, SrvrlsError> {
+///         Ok(PreTokenGenerationResponse::default().add_claim("tenant", "example"))
+///     }
+/// }
+/// ```
+pub trait SrvrlsCognitoApplication {
+    /// Handles the `PreSignUp_*` triggers.
+    ///
+    /// # Errors
+    /// An error rejects the sign up, the message of a `SrvrlsError::BadRequest` is returned to the
+    /// caller.
+    fn pre_sign_up(&mut self, _trigger: &CognitoTrigger, _request: PreSignUpRequest) -> Result<PreSignUpResponse, SrvrlsError> {
+        Ok(PreSignUpResponse::default())
+    }
+
+    /// Handles the `PostConfirmation_*` triggers.
+    ///
+    /// # Errors
+    /// An error is returned to the caller, the user remains confirmed.
+    fn post_confirmation(&mut self, _trigger: &CognitoTrigger, _request: PostConfirmationRequest) -> Result<(), SrvrlsError> {
+        Ok(())
+    }
+
+    /// Handles the `TokenGeneration_*` triggers.
+    ///
+    /// # Errors
+    /// An error fails the authentication.
+    fn pre_token_generation(&mut self, _trigger: &CognitoTrigger, _request: PreTokenGenerationRequest) -> Result<PreTokenGenerationResponse, SrvrlsError> {
+        Ok(PreTokenGenerationResponse::default())
+    }
+
+    /// Handles the `CustomMessage_*` triggers.
+    ///
+    /// # Errors
+    /// An error fails the operation that sends the message.
+    fn custom_message(&mut self, _trigger: &CognitoTrigger, _request: CustomMessageRequest) -> Result<CustomMessageResponse, SrvrlsError> {
+        Ok(CustomMessageResponse::default())
+    }
+}
+
+/// `SrvrlsCognito` wraps your application that implements `SrvrlsCognitoApplication` and interfaces
+/// with AWS Lambda for Cognito user pool triggers, dispatching by the `triggerSource` of the event.
+/// The event is returned to Cognito with only its' `response` replaced.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let cognito = SrvrlsCognito::new(UserPoolApplication {});
+///     lambda!(cognito);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsCognito<T: SrvrlsCognitoApplication> {
+    application: T,
+}
+
+impl<T: SrvrlsCognitoApplication> SrvrlsCognito<T> {
+    /// Create a new `SrvrlsCognito` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsCognito { application }
+    }
+
+    fn dispatch(&mut self, trigger: &CognitoTrigger, request: Value) -> Result<Value, SrvrlsError> {
+        let trigger_type = trigger.trigger_source.split('_').next().unwrap_or("");
+        Ok(match trigger_type {
+            "PreSignUp" => json!(self.application.pre_sign_up(trigger, trigger_request(trigger, request)?)?),
+            "PostConfirmation" => {
+                self.application.post_confirmation(trigger, trigger_request(trigger, request)?)?;
+                json!({})
+            }
+            "TokenGeneration" => self.application.pre_token_generation(trigger, trigger_request(trigger, request)?)?.to_value(),
+            "CustomMessage" => json!(self.application.custom_message(trigger, trigger_request(trigger, request)?)?),
+            _ => return Err(SrvrlsError::BadRequest(format!("unsupported trigger source '{}'", trigger.trigger_source))),
+        })
+    }
+}
+
+impl<T: SrvrlsCognitoApplication> Handler<Value, Value, HandlerError> for SrvrlsCognito<T> {
+    fn run(&mut self, mut event: Value, _ctx: Context) -> Result<Value, HandlerError> {
+        let trigger: CognitoTrigger = serde_json::from_value(event.clone())?;
+        match self.dispatch(&trigger, event["request"].clone()) {
+            Ok(response) => {
+                event["response"] = response;
+                Ok(event)
+            }
+            Err(e) => {
+                let message = match e {
+                    SrvrlsError::BadRequest(msg) | SrvrlsError::BadRequestWithSimpleMessage(msg) => msg,
+                    e => e.to_string(),
+                };
+                error!("trigger {} for {} failed: {}", trigger.trigger_source, trigger.user_name, message);
+                Err(HandlerError::from(message.as_str()))
+            }
+        }
+    }
+}
+
+fn trigger_request<R: DeserializeOwned>(trigger: &CognitoTrigger, request: Value) -> Result<R, SrvrlsError> {
+    serde_json::from_value(request)
+        .map_err(|e| SrvrlsError::BadRequest(format!("invalid {} request: {}", trigger.trigger_source, e)))
+}
+
+#[cfg(test)]
+mod cognito_tests {
+    use super::*;
+
+    struct UserPoolApplication {
+        confirmed: Vec<String>,
+    }
+
+    impl SrvrlsCognitoApplication for UserPoolApplication {
+        fn pre_sign_up(&mut self, _trigger: &CognitoTrigger, request: PreSignUpRequest) -> Result<PreSignUpResponse, SrvrlsError> {
+            match request.user_attributes.get("email") {
+                Some(email) if email.ends_with("@example.com") => Ok(PreSignUpResponse { auto_confirm_user: true, auto_verify_email: true, ..Default::default() }),
+                _ => Err(SrvrlsError::BadRequest("sign up is restricted to example.com".to_string())),
+            }
+        }
+
+        fn post_confirmation(&mut self, trigger: &CognitoTrigger, request: PostConfirmationRequest) -> Result<(), SrvrlsError> {
+            self.confirmed.push(format!("{}:{}", trigger.user_name, request.user_attributes["email"]));
+            Ok(())
+        }
+
+        fn pre_token_generation(&mut self, trigger: &CognitoTrigger, request: PreTokenGenerationRequest) -> Result<PreTokenGenerationResponse, SrvrlsError> {
+            Ok(PreTokenGenerationResponse::default()
+                .add_claim("tenant", &trigger.caller_context.client_id)
+                .add_claim("groups", &request.group_configuration.groups_to_override.join(","))
+                .suppress_claim("phone_number"))
+        }
+    }
+
+    fn event(trigger_source: &str, request: Value, response: Value) -> Value {
+        json!({
+            "version": "1",
+            "triggerSource": trigger_source,
+            "region": "us-east-1",
+            "userPoolId": "us-east-1_EXAMPLE",
+            "userName": "steve",
+            "callerContext": {"awsSdkVersion": "aws-sdk-unknown-unknown", "clientId": "7a8b9c"},
+            "request": request,
+            "response": response
+        })
+    }
+
+    #[test]
+    fn test_pre_sign_up() {
+        let mut cognito = SrvrlsCognito::new(UserPoolApplication { confirmed: Vec::new() });
+        let request = json!({"userAttributes": {"email": "steve@example.com"}, "validationData": null});
+        let response = cognito.run(event("PreSignUp_SignUp", request.clone(), json!({})), Context::default()).unwrap();
+        assert_eq!(event("PreSignUp_SignUp", request, json!({"autoConfirmUser": true, "autoVerifyEmail": true, "autoVerifyPhone": false})), response);
+
+        let request = json!({"userAttributes": {"email": "steve@example.org"}});
+        let error = cognito.run(event("PreSignUp_SignUp", request, json!({})), Context::default()).unwrap_err();
+        assert!(format!("{:?}", error).contains("sign up is restricted to example.com"));
+    }
+
+    #[test]
+    fn test_post_confirmation() {
+        let mut cognito = SrvrlsCognito::new(UserPoolApplication { confirmed: Vec::new() });
+        let request = json!({"userAttributes": {"email": "steve@example.com", "sub": "a1b2c3"}});
+        let response = cognito.run(event("PostConfirmation_ConfirmSignUp", request, json!({})), Context::default()).unwrap();
+        assert_eq!(json!({}), response["response"]);
+        assert_eq!(vec!["steve:steve@example.com".to_string()], cognito.application.confirmed);
+    }
+
+    #[test]
+    fn test_pre_token_generation() {
+        let mut cognito = SrvrlsCognito::new(UserPoolApplication { confirmed: Vec::new() });
+        let request = json!({
+            "userAttributes": {"email": "steve@example.com"},
+            "groupConfiguration": {"groupsToOverride": ["admin", "billing"], "iamRolesToOverride": [], "preferredRole": null}
+        });
+        let response = cognito.run(event("TokenGeneration_Authentication", request, json!({"claimsOverrideDetails": null})), Context::default()).unwrap();
+        assert_eq!(json!({"claimsOverrideDetails": {
+            "claimsToAddOrOverride": {"tenant": "7a8b9c", "groups": "admin,billing"},
+            "claimsToSuppress": ["phone_number"]
+        }}), response["response"]);
+    }
+
+    #[test]
+    fn test_default_custom_message() {
+        let mut cognito = SrvrlsCognito::new(UserPoolApplication { confirmed: Vec::new() });
+        let request = json!({"userAttributes": {}, "codeParameter": "{####}", "usernameParameter": null});
+        let response = cognito.run(event("CustomMessage_SignUp", request, json!({})), Context::default()).unwrap();
+        assert_eq!(json!({"smsMessage": null, "emailMessage": null, "emailSubject": null}), response["response"]);
+
+        assert!(cognito.run(event("UserMigration_Authentication", json!({}), json!({})), Context::default()).is_err());
+    }
+}
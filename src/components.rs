@@ -1,10 +1,21 @@
 use std::{fmt, error};
+use std::convert::Infallible;
+use std::str::FromStr;
+use std::collections::HashMap;
 use crate::response::SrvrlsResponse;
 
-/// Replaces a String match with an enum that only includes the most common `HttpMethod`s. This
-/// reduces overhead from trash methods like `CONNECT`, `OPTIONS` or `TRACE` as well as non-legit
-/// codes that are possible with `Strings`.
-/// Seriously, if you're using one of those you're probably just trolling your users.
+/// Replaces a String match with an enum of the `HttpMethod`s, any non-standard method is kept
+/// within `OTHER`. Methods can be compared directly or used within match statements.
+/// ```rust
+/// # use srvrls::components::HttpMethod;
+/// let method: HttpMethod = "patch".parse().unwrap();
+/// assert_eq!(HttpMethod::PATCH, method);
+/// assert_eq!("PATCH", method.to_string());
+///
+/// let method: HttpMethod = "PROPFIND".parse().unwrap();
+/// assert_eq!(HttpMethod::OTHER("PROPFIND".to_string()), method);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum HttpMethod {
     /// GET http method
     GET,
@@ -12,14 +23,54 @@ pub enum HttpMethod {
     POST,
     /// PUT http method
     PUT,
+    /// PATCH http method
+    PATCH,
     /// HEAD http method
     HEAD,
     /// DELETE http method
     DELETE,
-    /// One of the other http methods not implemented here
-    /// (intentionally, there is no reason to have 20 line match statements for the 1% of people
-    /// using `TRACE`)
-    OTHER,
+    /// OPTIONS http method
+    OPTIONS,
+    /// One of the other http methods (e.g., `TRACE` or `CONNECT`), this holds the original method.
+    OTHER(String),
+}
+
+impl fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpMethod::GET => write!(f, "GET"),
+            HttpMethod::POST => write!(f, "POST"),
+            HttpMethod::PUT => write!(f, "PUT"),
+            HttpMethod::PATCH => write!(f, "PATCH"),
+            HttpMethod::HEAD => write!(f, "HEAD"),
+            HttpMethod::DELETE => write!(f, "DELETE"),
+            HttpMethod::OPTIONS => write!(f, "OPTIONS"),
+            HttpMethod::OTHER(method) => write!(f, "{}", method),
+        }
+    }
+}
+
+impl From<&str> for HttpMethod {
+    fn from(method: &str) -> Self {
+        match method.to_ascii_uppercase().as_str() {
+            "GET" => HttpMethod::GET,
+            "POST" => HttpMethod::POST,
+            "PUT" => HttpMethod::PUT,
+            "PATCH" => HttpMethod::PATCH,
+            "HEAD" => HttpMethod::HEAD,
+            "DELETE" => HttpMethod::DELETE,
+            "OPTIONS" => HttpMethod::OPTIONS,
+            _ => HttpMethod::OTHER(method.to_string()),
+        }
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = Infallible;
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        Ok(HttpMethod::from(method))
+    }
 }
 
 /// You can always return the precise error response, but using the specific error allows a much
@@ -41,6 +92,10 @@ pub enum SrvrlsError {
     NotFound,
     /// Responds with a 405 - Method Not Allowed response
     MethodNotAllowed,
+    /// Responds with a 406 - Not Acceptable response
+    NotAcceptable,
+    /// Responds with a 413 - Payload Too Large response
+    PayloadTooLarge,
     /// Responds with a 500 - Internal Server Error response
     InternalServerError,
 }
@@ -61,7 +116,149 @@ impl fmt::Display for SrvrlsError {
             SrvrlsError::Forbidden => write!(f, "Forbidden"),
             SrvrlsError::NotFound => write!(f, "Not Found"),
             SrvrlsError::MethodNotAllowed => write!(f, "Method Not Allowed"),
+            SrvrlsError::NotAcceptable => write!(f, "Not Acceptable"),
+            SrvrlsError::PayloadTooLarge => write!(f, "Payload Too Large"),
             SrvrlsError::InternalServerError => write!(f, "InternalServerError"),
         }
     }
 }
+
+/// The error returned by applications handling non-HTTP events (e.g., SQS messages). Retryable
+/// errors cause the event to be redelivered, non-retryable errors are logged and the event is
+/// dropped. Failures are logged through the `log` facade, so a logger (e.g., `simple_logger`)
+/// must be initialized for them to be written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SrvrlsEventError {
+    /// A transient failure (e.g., a downstream timeout), the event should be delivered again.
+    Retryable(String),
+    /// A permanent failure (e.g., an invalid message), delivering the event again will not help.
+    NonRetryable(String),
+}
+
+impl error::Error for SrvrlsEventError {}
+
+impl fmt::Display for SrvrlsEventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SrvrlsEventError::Retryable(msg) => write!(f, "Retryable: {}", msg),
+            SrvrlsEventError::NonRetryable(msg) => write!(f, "Non-retryable: {}", msg),
+        }
+    }
+}
+
+/// Decodes a url encoded value, invalid escape sequences are left untouched. Query strings and
+/// form values also encode spaces as '+', use `plus_as_space` for these.
+pub(crate) fn percent_decode(value: &str, plus_as_space: bool) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high * 16 + low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parses an `application/x-www-form-urlencoded` string (e.g., a query string) into a multi-value
+/// map, keeping the order of repeated keys.
+pub(crate) fn parse_url_encoded(value: &str) -> HashMap<String, Vec<String>> {
+    let mut parameters: HashMap<String, Vec<String>> = HashMap::new();
+    for pair in value.split('&').filter(|pair| !pair.is_empty()) {
+        let mut key_value = pair.splitn(2, '=');
+        let key = percent_decode(key_value.next().unwrap_or(""), true);
+        let value = percent_decode(key_value.next().unwrap_or(""), true);
+        parameters.entry(key).or_default().push(value);
+    }
+    parameters
+}
+
+/// Splits a header value such as `form-data; name="file"; filename="a.txt"` into the leading value
+/// and a map of its' (lowercase) parameter names to their unquoted values.
+pub(crate) fn split_header_value(value: &str) -> (String, HashMap<String, String>) {
+    let mut segments = Vec::new();
+    let mut segment = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                segment.push(c);
+                escaped = false;
+            }
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => segments.push(std::mem::take(&mut segment)),
+            _ => segment.push(c),
+        }
+    }
+    segments.push(segment);
+    let mut segments = segments.into_iter();
+    let leading = segments.next().unwrap_or_default().trim().to_string();
+    let mut parameters = HashMap::new();
+    for parameter in segments {
+        let mut parameter = parameter.splitn(2, '=');
+        let key = parameter.next().unwrap_or("").trim().to_ascii_lowercase();
+        let value = parameter.next().unwrap_or("").trim().to_string();
+        if !key.is_empty() {
+            parameters.insert(key, value);
+        }
+    }
+    (leading, parameters)
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod components_tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!("ACME/EU", percent_decode("ACME%2FEU", false));
+        assert_eq!("a+b c", percent_decode("a+b%20c", false));
+        assert_eq!("a b c", percent_decode("a+b%20c", true));
+        assert_eq!("100%", percent_decode("100%", false));
+        assert_eq!("%zz%4", percent_decode("%zz%4", false));
+    }
+
+    #[test]
+    fn test_http_method() {
+        assert_eq!(HttpMethod::GET, "GET".parse().unwrap());
+        assert_eq!(HttpMethod::PATCH, "patch".parse().unwrap());
+        assert_eq!(HttpMethod::OPTIONS, "Options".parse().unwrap());
+        assert_eq!(HttpMethod::OTHER("TRACE".to_string()), "TRACE".parse().unwrap());
+        assert_ne!(HttpMethod::OTHER("TRACE".to_string()), HttpMethod::OTHER("CONNECT".to_string()));
+
+        for method in &["GET", "POST", "PUT", "PATCH", "HEAD", "DELETE", "OPTIONS", "TRACE"] {
+            assert_eq!(*method, method.parse::<HttpMethod>().unwrap().to_string());
+        }
+    }
+
+    #[test]
+    fn test_parse_url_encoded() {
+        let parameters = parse_url_encoded("id=CUST%2DA23948&tag=a&tag=b+c&empty=&flag");
+        assert_eq!(vec!["CUST-A23948".to_string()], parameters["id"]);
+        assert_eq!(vec!["a".to_string(), "b c".to_string()], parameters["tag"]);
+        assert_eq!(vec!["".to_string()], parameters["empty"]);
+        assert_eq!(vec!["".to_string()], parameters["flag"]);
+    }
+}
@@ -0,0 +1,180 @@
+use std::io::Write;
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use crate::components::{split_header_value, SrvrlsError};
+
+/// The content encodings supported for response compression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    /// Brotli (`br`) encoding.
+    Brotli,
+    /// Gzip (`gzip`) encoding.
+    Gzip,
+    /// Zlib wrapped deflate (`deflate`) encoding.
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// The token used for this encoding in the `Accept-Encoding` and `Content-Encoding` headers.
+    #[must_use]
+    pub fn token(&self) -> &str {
+        match self {
+            ContentEncoding::Brotli => "br",
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+}
+
+/// Configuration of response compression, see `Srvrls::with_compression`.
+/// ```rust
+/// # use srvrls::compression::{Compression, ContentEncoding};
+/// let compression = Compression {
+///     threshold: 8 * 1024,
+///     encodings: vec![ContentEncoding::Gzip],
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compression {
+    /// The minimum size in bytes of a response body before it is compressed, defaults to 1 KB.
+    pub threshold: usize,
+    /// The supported encodings in order of preference, used when the client accepts more than one
+    /// encoding with the same quality.
+    pub encodings: Vec<ContentEncoding>,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            threshold: 1024,
+            encodings: vec![ContentEncoding::Brotli, ContentEncoding::Gzip, ContentEncoding::Deflate],
+        }
+    }
+}
+
+impl Compression {
+    /// Whether a body of this size and content type should be compressed.
+    pub(crate) fn applies_to(&self, content_type: &str, length: usize) -> bool {
+        length >= self.threshold && is_compressible(content_type)
+    }
+
+    /// Selects the supported encoding with the highest quality in the `Accept-Encoding` header.
+    pub(crate) fn select(&self, accept_encoding: &str) -> Option<ContentEncoding> {
+        let codings: Vec<(String, f32)> = accept_encoding.split(',')
+            .map(|coding| {
+                let (coding, parameters) = split_header_value(coding);
+                let quality = parameters.get("q").and_then(|q| q.parse().ok()).unwrap_or(1.0);
+                (coding.to_ascii_lowercase(), quality)
+            })
+            .collect();
+        let mut selected = None;
+        let mut selected_quality = 0.0;
+        for encoding in &self.encodings {
+            let quality = codings.iter().find(|(coding, _)| coding == encoding.token())
+                .or_else(|| codings.iter().find(|(coding, _)| coding == "*"))
+                .map_or(0.0, |(_, quality)| *quality);
+            if quality > selected_quality {
+                selected = Some(*encoding);
+                selected_quality = quality;
+            }
+        }
+        selected
+    }
+}
+
+/// Media types that are already compressed and gain nothing from further compression.
+const COMPRESSED_CONTENT_TYPES: &[&str] = &[
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+    "application/zstd",
+    "font/woff",
+    "font/woff2",
+];
+
+fn is_compressible(content_type: &str) -> bool {
+    let (media_type, _) = split_header_value(content_type);
+    let media_type = media_type.to_ascii_lowercase();
+    if media_type == "image/svg+xml" {
+        return true;
+    }
+    let main_type = media_type.split('/').next().unwrap_or("");
+    !matches!(main_type, "image" | "audio" | "video") && !COMPRESSED_CONTENT_TYPES.contains(&media_type.as_str())
+}
+
+pub(crate) fn compress(encoding: ContentEncoding, body: &[u8]) -> Result<Vec<u8>, SrvrlsError> {
+    let compressed = match encoding {
+        ContentEncoding::Brotli => {
+            let mut compressed = Vec::new();
+            {
+                let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+                writer.write_all(body).map_err(|_| SrvrlsError::InternalServerError)?;
+            }
+            Ok(compressed)
+        }
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body).and_then(|_| encoder.finish())
+        }
+        ContentEncoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body).and_then(|_| encoder.finish())
+        }
+    };
+    compressed.map_err(|_| SrvrlsError::InternalServerError)
+}
+
+#[cfg(test)]
+mod compression_tests {
+    use std::io::Read;
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let compression = Compression::default();
+        assert_eq!(Some(ContentEncoding::Brotli), compression.select("gzip, deflate, br"));
+        assert_eq!(Some(ContentEncoding::Gzip), compression.select("gzip;q=1.0, br;q=0.5"));
+        assert_eq!(Some(ContentEncoding::Deflate), compression.select("deflate"));
+        assert_eq!(Some(ContentEncoding::Gzip), compression.select("*, br;q=0"));
+        assert_eq!(None, compression.select("identity"));
+        assert_eq!(None, compression.select(""));
+        assert_eq!(None, compression.select("gzip;q=0"));
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let compression = Compression::default();
+        assert!(compression.applies_to("application/json", 2048));
+        assert!(compression.applies_to("image/svg+xml; charset=utf-8", 2048));
+        assert!(!compression.applies_to("application/json", 512));
+        assert!(!compression.applies_to("image/png", 2048));
+        assert!(!compression.applies_to("application/zip", 2048));
+    }
+
+    #[test]
+    fn test_compress() {
+        let body = "srvrls ".repeat(100).into_bytes();
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(&compress(ContentEncoding::Gzip, &body).unwrap()[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(body, decoded);
+
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(&compress(ContentEncoding::Deflate, &body).unwrap()[..]).read_to_end(&mut decoded).unwrap();
+        assert_eq!(body, decoded);
+
+        let compressed = compress(ContentEncoding::Brotli, &body).unwrap();
+        assert!(compressed.len() < body.len());
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(&compressed[..], 4096).read_to_end(&mut decoded).unwrap();
+        assert_eq!(body, decoded);
+    }
+}
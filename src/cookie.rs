@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// The `SameSite` attribute of a response cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    /// The cookie is only sent with same-site requests.
+    Strict,
+    /// The cookie is also sent with top level cross-site navigation.
+    Lax,
+    /// The cookie is sent with all requests, browsers require this to be combined with `Secure`.
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+/// A response cookie, rendered as a `Set-Cookie` header.
+/// ```rust
+/// # use srvrls::cookie::{Cookie, SameSite};
+/// let cookie = Cookie::new("session", "38afes7a8")
+///     .secure()
+///     .http_only()
+///     .same_site(SameSite::Lax)
+///     .max_age(3600)
+///     .path("/");
+/// assert_eq!("session=38afes7a8; Max-Age=3600; Path=/; Secure; HttpOnly; SameSite=Lax", cookie.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    /// The cookie name.
+    pub name: String,
+    /// The cookie value, this is sent as provided and should not contain `;`, `,` or whitespace.
+    pub value: String,
+    /// Whether the cookie is only sent over HTTPS.
+    pub secure: bool,
+    /// Whether the cookie is hidden from client side scripts.
+    pub http_only: bool,
+    /// The `SameSite` attribute, if any.
+    pub same_site: Option<SameSite>,
+    /// The lifetime of the cookie in seconds, zero or less expires the cookie immediately.
+    pub max_age: Option<i64>,
+    /// The domain the cookie is sent to.
+    pub domain: Option<String>,
+    /// The path the cookie is sent to.
+    pub path: Option<String>,
+}
+
+impl Cookie {
+    /// Create a new session cookie with no attributes.
+    #[must_use]
+    pub fn new(name: &str, value: &str) -> Self {
+        Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            secure: false,
+            http_only: false,
+            same_site: None,
+            max_age: None,
+            domain: None,
+            path: None,
+        }
+    }
+
+    /// Create a cookie that instructs the client to remove any existing cookie with this name.
+    #[must_use]
+    pub fn removal(name: &str) -> Self {
+        Cookie::new(name, "").max_age(0)
+    }
+
+    /// Sets the `Secure` attribute.
+    #[must_use]
+    pub fn secure(mut self) -> Self {
+        self.secure = true;
+        self
+    }
+
+    /// Sets the `HttpOnly` attribute.
+    #[must_use]
+    pub fn http_only(mut self) -> Self {
+        self.http_only = true;
+        self
+    }
+
+    /// Sets the `SameSite` attribute.
+    #[must_use]
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Sets the `Max-Age` attribute in seconds.
+    #[must_use]
+    pub fn max_age(mut self, seconds: i64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Sets the `Domain` attribute.
+    #[must_use]
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Sets the `Path` attribute.
+    #[must_use]
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the cookies of a `Cookie` request header to the jar, the first value for any name is kept.
+pub(crate) fn parse_cookie_header(header: &str, jar: &mut HashMap<String, String>) {
+    for pair in header.split(';') {
+        let mut pair = pair.splitn(2, '=');
+        let name = pair.next().unwrap_or("").trim();
+        let value = pair.next().unwrap_or("").trim().trim_matches('"');
+        if !name.is_empty() {
+            jar.entry(name.to_string()).or_insert_with(|| value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod cookie_tests {
+    use super::*;
+
+    #[test]
+    fn test_cookie_display() {
+        assert_eq!("theme=dark", Cookie::new("theme", "dark").to_string());
+        assert_eq!("session=; Max-Age=0", Cookie::removal("session").to_string());
+
+        let cookie = Cookie::new("session", "abc")
+            .domain("example.com")
+            .path("/app")
+            .secure()
+            .same_site(SameSite::None);
+        assert_eq!("session=abc; Domain=example.com; Path=/app; Secure; SameSite=None", cookie.to_string());
+    }
+
+    #[test]
+    fn test_parse_cookie_header() {
+        let mut jar = HashMap::new();
+        parse_cookie_header("session=abc; theme=\"dark\";flag; session=ignored", &mut jar);
+        parse_cookie_header("lang=en", &mut jar);
+        assert_eq!("abc", jar["session"]);
+        assert_eq!("dark", jar["theme"]);
+        assert_eq!("", jar["flag"]);
+        assert_eq!("en", jar["lang"]);
+    }
+}
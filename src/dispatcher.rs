@@ -0,0 +1,370 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use aws_lambda_events::event::alb::AlbTargetGroupRequest;
+use aws_lambda_events::event::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyResponse};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::application::{Srvrls, SrvrlsApplication};
+use crate::events::{alb_proxy_request, alb_response, http_api_proxy_request, HttpApiRequest, HttpApiResponse};
+
+/// The AWS service an event was delivered by, as detected from the shape of the raw event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventSource {
+    /// An API Gateway REST API proxy event (payload format version 1.0).
+    ApiGateway,
+    /// An API Gateway HTTP API event (payload format version 2.0).
+    HttpApi,
+    /// A Lambda Function URL event, this uses the HTTP API payload format.
+    FunctionUrl,
+    /// An Application Load Balancer target group event.
+    Alb,
+    /// An API Gateway WebSocket API event.
+    WebSocket,
+    /// A batch of SQS messages.
+    Sqs,
+    /// An SNS notification.
+    Sns,
+    /// An EventBridge (CloudWatch Events) event.
+    EventBridge,
+    /// A batch of S3 object notifications.
+    S3,
+}
+
+impl EventSource {
+    /// Detects the source of a raw event from its' shape, returning `None` if the event is not
+    /// recognized.
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use srvrls::dispatcher::EventSource;
+    /// let event = json!({"Records": [{"eventSource": "aws:sqs", "body": "{}"}]});
+    /// assert_eq!(Some(EventSource::Sqs), EventSource::detect(&event));
+    /// ```
+    #[must_use]
+    pub fn detect(event: &Value) -> Option<EventSource> {
+        let context = &event["requestContext"];
+        if context.is_object() {
+            return if context["elb"].is_object() {
+                Some(EventSource::Alb)
+            } else if context["connectionId"].is_string() && context["eventType"].is_string() {
+                Some(EventSource::WebSocket)
+            } else if event["version"] == "2.0" {
+                match context["domainName"].as_str() {
+                    Some(domain_name) if domain_name.contains(".lambda-url.") => Some(EventSource::FunctionUrl),
+                    _ => Some(EventSource::HttpApi),
+                }
+            } else if event["httpMethod"].is_string() {
+                Some(EventSource::ApiGateway)
+            } else {
+                None
+            };
+        }
+        let record = &event["Records"][0];
+        if record.is_object() {
+            let source = record["eventSource"].as_str().or_else(|| record["EventSource"].as_str());
+            return match source {
+                Some("aws:sqs") => Some(EventSource::Sqs),
+                Some("aws:sns") => Some(EventSource::Sns),
+                Some("aws:s3") => Some(EventSource::S3),
+                _ => None,
+            };
+        }
+        if event["detail-type"].is_string() && event["source"].is_string() {
+            return Some(EventSource::EventBridge);
+        }
+        None
+    }
+}
+
+type Route = Box<dyn FnMut(Value, Context) -> Result<Value, HandlerError>>;
+type WarmUpCheck = Box<dyn FnMut(&Value, &Context) -> bool>;
+
+/// `SrvrlsDispatcher` is a single AWS Lambda entry point for functions that are triggered by more
+/// than one event source. Each raw event is inspected to detect its' `EventSource` and is then
+/// passed to the handler registered for that source.
+///
+/// An event that is not recognized, or that has no registered handler, fails the invocation with
+/// an explicit error rather than a deserialization failure. The exception is a warm-up ping
+/// matching the warm-up payloads of the registered `Srvrls` instance, see `Srvrls::with_warm_up`,
+/// which is answered without calling any handler.
+/// ```rust
+/// # use srvrls::application::{Srvrls, SrvrlsApplication};
+/// # use srvrls::components::{SrvrlsError, SrvrlsEventError};
+/// # use srvrls::dispatcher::{EventSource, SrvrlsDispatcher};
+/// # use srvrls::queue::{QueueMessage, SrvrlsQueue, SrvrlsQueueApplication};
+/// # use srvrls::request::SrvrlsRequest;
+/// # use srvrls::response::SrvrlsResponse;
+/// # struct OrderApi {}
+/// # impl SrvrlsApplication for OrderApi {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+/// #         Ok(SrvrlsResponse::ok_empty())
+/// #     }
+/// # }
+/// # struct OrderQueue {}
+/// # impl SrvrlsQueueApplication for OrderQueue {
+/// #     type Message = serde_json::Value;
+/// #     fn handle(&mut self, message: QueueMessage<serde_json::Value>) -> Result<(), SrvrlsEventError> { Ok(()) }
+/// # }
+/// let mut dispatcher = SrvrlsDispatcher::new();
+/// dispatcher.with_http(Srvrls::new(OrderApi {}));
+/// dispatcher.with_handler(EventSource::Sqs, SrvrlsQueue::new(OrderQueue {}));
+/// ```
+/// This `SrvrlsDispatcher` object is then used to build your lambda application within the `main`.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let dispatcher = build_dispatcher();
+///     lambda!(dispatcher);
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct SrvrlsDispatcher {
+    routes: HashMap<EventSource, Route>,
+    warm_up: Option<WarmUpCheck>,
+}
+
+impl SrvrlsDispatcher {
+    /// Create a new `SrvrlsDispatcher` instance with no registered handlers.
+    #[must_use]
+    pub fn new() -> Self {
+        SrvrlsDispatcher::default()
+    }
+
+    /// Registers the handler for all events from the provided source, replacing any handler
+    /// previously registered for it. The raw event is deserialized into the handler's event type
+    /// `E`.
+    pub fn with_handler<E, O, H>(&mut self, source: EventSource, mut handler: H)
+        where E: DeserializeOwned,
+              O: Serialize,
+              H: Handler<E, O, HandlerError> + 'static
+    {
+        let route = move |event: Value, ctx: Context| run_handler(&mut handler, event, ctx);
+        self.routes.insert(source, Box::new(route));
+    }
+
+    /// Registers a `Srvrls` instance as the handler for all HTTP events, i.e., API Gateway REST and
+    /// HTTP APIs, Function URLs and Application Load Balancers. Warm-up pings that no other handler
+    /// is registered for are answered using the warm-up configuration of the `Srvrls` instance.
+    ///
+    /// ALB target groups must have multi-value headers enabled to return more than one cookie,
+    /// otherwise only the last `Set-Cookie` header is returned and a warning is logged.
+    pub fn with_http<T: SrvrlsApplication + 'static>(&mut self, srvrls: Srvrls<T>) {
+        let srvrls = Rc::new(RefCell::new(srvrls));
+        let rest = srvrls.clone();
+        self.routes.insert(EventSource::ApiGateway, Box::new(move |event, ctx| {
+            run_handler::<ApiGatewayProxyRequest, ApiGatewayProxyResponse, _>(&mut *rest.borrow_mut(), event, ctx)
+        }));
+        for source in &[EventSource::HttpApi, EventSource::FunctionUrl] {
+            let http = srvrls.clone();
+            self.routes.insert(*source, Box::new(move |event, ctx| {
+                let request: HttpApiRequest = serde_json::from_value(event)?;
+                let response = http.borrow_mut().run(http_api_proxy_request(request), ctx)?;
+                Ok(serde_json::to_value(HttpApiResponse::from(response))?)
+            }));
+        }
+        let alb = srvrls.clone();
+        self.routes.insert(EventSource::Alb, Box::new(move |event, ctx| {
+            let multi_value_headers = event["multiValueHeaders"].is_object();
+            let request: AlbTargetGroupRequest = serde_json::from_value(event)?;
+            let response = alb.borrow_mut().run(alb_proxy_request(request), ctx)?;
+            Ok(serde_json::to_value(alb_response(response, multi_value_headers))?)
+        }));
+        self.warm_up = Some(Box::new(move |event, ctx| srvrls.borrow_mut().warm_up(event, ctx)));
+    }
+
+    fn dispatch(&mut self, event: Value, ctx: Context) -> Result<Value, HandlerError> {
+        let source = EventSource::detect(&event);
+        if let Some(route) = source.and_then(|source| self.routes.get_mut(&source)) {
+            return route(event, ctx);
+        }
+        if let Some(warm_up) = &mut self.warm_up {
+            if warm_up(&event, &ctx) {
+                return Ok(Value::Null);
+            }
+        }
+        match source {
+            Some(source) => Err(HandlerError::from(format!("no handler registered for {:?} events", source).as_str())),
+            None => {
+                let keys = match &event {
+                    Value::Object(fields) => fields.keys().cloned().collect::<Vec<String>>().join(", "),
+                    _ => "none".to_string(),
+                };
+                Err(HandlerError::from(format!("unrecognized event with keys: {}", keys).as_str()))
+            }
+        }
+    }
+}
+
+impl Handler<Value, Value, HandlerError> for SrvrlsDispatcher {
+    fn run(&mut self, event: Value, ctx: Context) -> Result<Value, HandlerError> {
+        self.dispatch(event, ctx)
+    }
+}
+
+fn run_handler<E, O, H>(handler: &mut H, event: Value, ctx: Context) -> Result<Value, HandlerError>
+    where E: DeserializeOwned,
+          O: Serialize,
+          H: Handler<E, O, HandlerError>
+{
+    let event: E = serde_json::from_value(event)?;
+    let output = handler.run(event, ctx)?;
+    Ok(serde_json::to_value(output)?)
+}
+
+#[cfg(test)]
+mod dispatcher_tests {
+    use serde_json::json;
+
+    use crate::components::{SrvrlsError, SrvrlsEventError};
+    use crate::notification::{Notification, SrvrlsNotification, SrvrlsNotificationApplication};
+    use crate::queue::{QueueMessage, SrvrlsQueue, SrvrlsQueueApplication};
+    use crate::request::SrvrlsRequest;
+    use crate::response::SrvrlsResponse;
+
+    use super::*;
+
+    struct EchoApplication {}
+
+    impl SrvrlsApplication for EchoApplication {
+        fn handle(&mut self, event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+            Ok(SrvrlsResponse::ok(json!({"path": event.path})))
+        }
+    }
+
+    struct CountApplication {
+        count: Rc<RefCell<usize>>,
+    }
+
+    impl SrvrlsQueueApplication for CountApplication {
+        type Message = Value;
+
+        fn handle(&mut self, _message: QueueMessage<Value>) -> Result<(), SrvrlsEventError> {
+            *self.count.borrow_mut() += 1;
+            Ok(())
+        }
+    }
+
+    impl SrvrlsNotificationApplication for CountApplication {
+        type Message = Value;
+
+        fn handle(&mut self, _notification: Notification<Value>) -> Result<(), SrvrlsEventError> {
+            *self.count.borrow_mut() += 10;
+            Ok(())
+        }
+    }
+
+    fn sqs_event() -> Value {
+        json!({"Records": [{
+            "messageId": "059f36b4-87a3-44ab-83d2-661975830a7d",
+            "body": "{}",
+            "eventSource": "aws:sqs",
+            "eventSourceARN": "arn:aws:sqs:us-east-1:123456789012:orders"
+        }]})
+    }
+
+    fn sns_event() -> Value {
+        json!({"Records": [{
+            "EventSource": "aws:sns",
+            "EventVersion": "1.0",
+            "EventSubscriptionArn": "arn:aws:sns:us-east-1:123456789012:prices:2bcfbf39",
+            "Sns": {
+                "Type": "Notification",
+                "MessageId": "95df01b4-ee98-5cb9-9903-4c221d41eb5e",
+                "TopicArn": "arn:aws:sns:us-east-1:123456789012:prices",
+                "Subject": null,
+                "Message": "{}",
+                "Timestamp": "2020-10-08T16:53:06.000Z",
+                "SignatureVersion": "1",
+                "Signature": "EXAMPLE",
+                "SigningCertUrl": "EXAMPLE",
+                "UnsubscribeUrl": "EXAMPLE",
+                "MessageAttributes": {}
+            }
+        }]})
+    }
+
+    fn http_api_event(domain_name: &str) -> Value {
+        json!({
+            "version": "2.0",
+            "rawPath": "/orders/1",
+            "rawQueryString": "",
+            "requestContext": {"domainName": domain_name, "http": {"method": "GET"}},
+            "isBase64Encoded": false
+        })
+    }
+
+    fn alb_event() -> Value {
+        json!({
+            "httpMethod": "GET",
+            "path": "/orders/1",
+            "multiValueQueryStringParameters": {},
+            "multiValueHeaders": {"accept": ["application/json"]},
+            "requestContext": {"elb": {"targetGroupArn": "arn:aws:elasticloadbalancing:us-east-1:123456789012:targetgroup/srvrls/1"}},
+            "isBase64Encoded": false,
+            "body": ""
+        })
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Some(EventSource::Sqs), EventSource::detect(&sqs_event()));
+        assert_eq!(Some(EventSource::Sns), EventSource::detect(&sns_event()));
+        assert_eq!(Some(EventSource::S3), EventSource::detect(&json!({"Records": [{"eventSource": "aws:s3"}]})));
+        assert_eq!(Some(EventSource::HttpApi), EventSource::detect(&http_api_event("api.example.com")));
+        assert_eq!(Some(EventSource::FunctionUrl), EventSource::detect(&http_api_event("abc123.lambda-url.us-east-1.on.aws")));
+        assert_eq!(Some(EventSource::Alb), EventSource::detect(&alb_event()));
+        assert_eq!(Some(EventSource::ApiGateway), EventSource::detect(&json!({"httpMethod": "GET", "requestContext": {}})));
+        assert_eq!(Some(EventSource::WebSocket), EventSource::detect(&json!({
+            "requestContext": {"routeKey": "$connect", "eventType": "CONNECT", "connectionId": "L0SM9cOFvHcCIhw="}
+        })));
+        assert_eq!(Some(EventSource::EventBridge), EventSource::detect(&json!({
+            "detail-type": "Scheduled Event", "source": "aws.events", "detail": {}
+        })));
+        assert_eq!(None, EventSource::detect(&json!({"Records": [{"eventSource": "aws:ses"}]})));
+        assert_eq!(None, EventSource::detect(&json!({"source": "serverless-plugin-warmup"})));
+        assert_eq!(None, EventSource::detect(&json!("ping")));
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let count = Rc::new(RefCell::new(0));
+        let mut dispatcher = SrvrlsDispatcher::new();
+        dispatcher.with_http(Srvrls::new(EchoApplication {}));
+        dispatcher.with_handler(EventSource::Sqs, SrvrlsQueue::new(CountApplication { count: count.clone() }));
+        dispatcher.with_handler(EventSource::Sns, SrvrlsNotification::new(CountApplication { count: count.clone() }));
+
+        let response = dispatcher.run(sqs_event(), Context::default()).unwrap();
+        assert_eq!(json!({"batchItemFailures": []}), response);
+        dispatcher.run(sns_event(), Context::default()).unwrap();
+        assert_eq!(11, *count.borrow());
+
+        let response = dispatcher.run(http_api_event("abc123.lambda-url.us-east-1.on.aws"), Context::default()).unwrap();
+        assert_eq!(200, response["statusCode"]);
+        assert_eq!(r#"{"path":"orders/1"}"#, response["body"]);
+
+        let response = dispatcher.run(alb_event(), Context::default()).unwrap();
+        assert_eq!("200 OK", response["statusDescription"]);
+        assert_eq!(r#"{"path":"orders/1"}"#, response["body"]);
+        assert!(response["multiValueHeaders"]["Content-Type"].is_array());
+    }
+
+    #[test]
+    fn test_unrecognized_event() {
+        let mut dispatcher = SrvrlsDispatcher::new();
+        dispatcher.with_http(Srvrls::new(EchoApplication {}));
+
+        let error = dispatcher.run(json!({"Records": [{"eventSource": "aws:ses"}]}), Context::default()).unwrap_err();
+        assert!(format!("{:?}", error).contains("unrecognized event with keys: Records"));
+
+        let error = dispatcher.run(sqs_event(), Context::default()).unwrap_err();
+        assert!(format!("{:?}", error).contains("no handler registered for Sqs events"));
+
+        let response = dispatcher.run(json!({"source": "serverless-plugin-warmup"}), Context::default()).unwrap();
+        assert_eq!(Value::Null, response);
+    }
+}
@@ -0,0 +1,220 @@
+use aws_lambda_events::event::cloudwatch_events::CloudWatchEvent;
+use chrono::{DateTime, Utc};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::{error, warn};
+use serde::de::DeserializeOwned;
+
+use crate::components::SrvrlsEventError;
+
+/// An EventBridge (CloudWatch Events) event with its' `detail` deserialized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventBridgeEvent<D> {
+    /// The event id.
+    pub id: String,
+    /// The source of the event, e.g. `com.example.orders`.
+    pub source: String,
+    /// The detail type of the event, e.g. `Order Placed`.
+    pub detail_type: String,
+    /// The AWS account the event originated in.
+    pub account: String,
+    /// The AWS region the event originated in.
+    pub region: String,
+    /// The time of the event.
+    pub time: DateTime<Utc>,
+    /// The ARNs of any resources involved in the event.
+    pub resources: Vec<String>,
+    /// The deserialized event detail.
+    pub detail: D,
+}
+
+/// An event from a scheduled (cron or rate) rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledEvent {
+    /// The event id.
+    pub id: String,
+    /// The ARNs of the rules that triggered the event.
+    pub rules: Vec<String>,
+    /// The scheduled time of the event.
+    pub time: DateTime<Utc>,
+}
+
+type Route = Box<dyn FnMut(CloudWatchEvent) -> Result<(), SrvrlsEventError>>;
+type ScheduleHandler = Box<dyn FnMut(ScheduledEvent) -> Result<(), SrvrlsEventError>>;
+
+/// `SrvrlsEventBridge` interfaces with AWS Lambda for EventBridge events, dispatching each event by
+/// its' `source` and `detail-type` to the handler registered for that route. Events from scheduled
+/// rules (`aws.events` / `Scheduled Event`) are passed to the schedule handler.
+///
+/// A retryable error fails the invocation so that AWS Lambda retries the (asynchronous) delivery.
+/// A non-retryable error, a detail that cannot be deserialized or an event with no matching route
+/// (which would never match on a retry) is logged and the event is dropped.
+/// ```rust
+/// # use serde::Deserialize;
+/// # use srvrls::eventbridge::{EventBridgeEvent, SrvrlsEventBridge};
+/// #[derive(Deserialize)]
+/// struct OrderPlaced {
+///     order_id: String,
+/// }
+///
+/// let mut eventbridge = SrvrlsEventBridge::new();
+/// eventbridge.with_route("com.example.orders", "Order Placed", |event: EventBridgeEvent<OrderPlaced>| {
+///     println!("order placed {}", event.detail.order_id);
+///     Ok(())
+/// });
+/// eventbridge.with_schedule(|event| {
+///     println!("nightly cleanup at {}", event.time);
+///     Ok(())
+/// });
+/// ```
+/// This `SrvrlsEventBridge` object is then used to build your lambda application within the `main`.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let eventbridge = build_eventbridge();
+///     lambda!(eventbridge);
+///     Ok(())
+/// }
+/// ```
+#[derive(Default)]
+pub struct SrvrlsEventBridge {
+    routes: Vec<(String, String, Route)>,
+    schedule: Option<ScheduleHandler>,
+}
+
+impl SrvrlsEventBridge {
+    /// Create a new `SrvrlsEventBridge` instance with no routes.
+    #[must_use]
+    pub fn new() -> Self {
+        SrvrlsEventBridge::default()
+    }
+
+    /// Registers a handler for all events with the provided `source` and `detail-type`, the event
+    /// `detail` is deserialized into `D`.
+    pub fn with_route<D, F>(&mut self, source: &str, detail_type: &str, mut handler: F)
+        where D: DeserializeOwned + 'static,
+              F: FnMut(EventBridgeEvent<D>) -> Result<(), SrvrlsEventError> + 'static
+    {
+        let route = move |event: CloudWatchEvent| {
+            let detail = serde_json::from_value(event.detail)
+                .map_err(|e| SrvrlsEventError::NonRetryable(format!("unable to deserialize detail: {}", e)))?;
+            handler(EventBridgeEvent {
+                id: event.id.unwrap_or_default(),
+                source: event.source.unwrap_or_default(),
+                detail_type: event.detail_type.unwrap_or_default(),
+                account: event.account_id.unwrap_or_default(),
+                region: event.region.unwrap_or_default(),
+                time: event.time,
+                resources: event.resources,
+                detail,
+            })
+        };
+        self.routes.push((source.to_string(), detail_type.to_string(), Box::new(route)));
+    }
+
+    /// Registers the handler for events from scheduled rules.
+    pub fn with_schedule<F>(&mut self, handler: F)
+        where F: FnMut(ScheduledEvent) -> Result<(), SrvrlsEventError> + 'static
+    {
+        self.schedule = Some(Box::new(handler));
+    }
+
+    fn dispatch(&mut self, event: CloudWatchEvent) -> Result<(), SrvrlsEventError> {
+        let source = event.source.clone().unwrap_or_default();
+        let detail_type = event.detail_type.clone().unwrap_or_default();
+        if let (Some(schedule), "aws.events", "Scheduled Event") = (&mut self.schedule, source.as_str(), detail_type.as_str()) {
+            return schedule(ScheduledEvent {
+                id: event.id.unwrap_or_default(),
+                rules: event.resources,
+                time: event.time,
+            });
+        }
+        match self.routes.iter_mut().find(|(s, d, _)| *s == source && *d == detail_type) {
+            Some((_, _, route)) => route(event),
+            None => Err(SrvrlsEventError::NonRetryable(format!("no route for source '{}' and detail-type '{}'", source, detail_type))),
+        }
+    }
+}
+
+impl Handler<CloudWatchEvent, (), HandlerError> for SrvrlsEventBridge {
+    fn run(&mut self, event: CloudWatchEvent, _ctx: Context) -> Result<(), HandlerError> {
+        let id = event.id.clone().unwrap_or_default();
+        match self.dispatch(event) {
+            Ok(()) => Ok(()),
+            Err(SrvrlsEventError::Retryable(msg)) => {
+                warn!("event {} failed and will be retried: {}", id, msg);
+                Err(HandlerError::from(msg.as_str()))
+            }
+            Err(SrvrlsEventError::NonRetryable(msg)) => {
+                error!("event {} failed and will not be retried: {}", id, msg);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod eventbridge_tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct OrderPlaced {
+        order_id: String,
+    }
+
+    fn event(source: &str, detail_type: &str, detail: serde_json::Value) -> CloudWatchEvent {
+        serde_json::from_value(json!({
+            "version": "0",
+            "id": "53dc4d37-cffa-4f76-80c9-8b7d4a4d2eaa",
+            "detail-type": detail_type,
+            "source": source,
+            "account": "123456789012",
+            "time": "2020-10-08T16:53:06Z",
+            "region": "us-east-1",
+            "resources": ["arn:aws:events:us-east-1:123456789012:rule/nightly"],
+            "detail": detail
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let received = Rc::new(RefCell::new(Vec::new()));
+        let mut eventbridge = SrvrlsEventBridge::new();
+        let orders = received.clone();
+        eventbridge.with_route("com.example.orders", "Order Placed", move |event: EventBridgeEvent<OrderPlaced>| {
+            orders.borrow_mut().push(format!("{}:{}", event.detail_type, event.detail.order_id));
+            Ok(())
+        });
+        let schedules = received.clone();
+        eventbridge.with_schedule(move |event| {
+            schedules.borrow_mut().push(format!("scheduled:{}", event.rules[0]));
+            Ok(())
+        });
+
+        eventbridge.run(event("com.example.orders", "Order Placed", json!({"order_id": "ORD-1"})), Context::default()).unwrap();
+        eventbridge.run(event("aws.events", "Scheduled Event", json!({})), Context::default()).unwrap();
+        assert_eq!(vec!["Order Placed:ORD-1".to_string(), "scheduled:arn:aws:events:us-east-1:123456789012:rule/nightly".to_string()],
+                   *received.borrow());
+
+        assert_eq!(Err(SrvrlsEventError::NonRetryable("no route for source 'com.example.orders' and detail-type 'Order Shipped'".to_string())),
+                   eventbridge.dispatch(event("com.example.orders", "Order Shipped", json!({}))));
+        assert!(eventbridge.run(event("com.example.orders", "Order Shipped", json!({})), Context::default()).is_ok());
+        assert!(eventbridge.run(event("com.example.orders", "Order Placed", json!({"id": 1})), Context::default()).is_ok());
+        assert_eq!(2, received.borrow().len());
+    }
+
+    #[test]
+    fn test_retryable_error() {
+        let mut eventbridge = SrvrlsEventBridge::new();
+        eventbridge.with_route("com.example.orders", "Order Placed", |_event: EventBridgeEvent<serde_json::Value>| {
+            Err(SrvrlsEventError::Retryable("warehouse unavailable".to_string()))
+        });
+        assert!(eventbridge.run(event("com.example.orders", "Order Placed", json!({})), Context::default()).is_err());
+    }
+}
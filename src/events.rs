@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::alb::{AlbTargetGroupRequest, AlbTargetGroupResponse};
+use aws_lambda_events::event::apigw::{ApiGatewayProxyRequest, ApiGatewayProxyRequestContext, ApiGatewayProxyResponse, ApiGatewayRequestIdentity};
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::components::{parse_url_encoded, percent_decode};
+
+/// The API Gateway HTTP API request (payload format version 2.0).
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiRequest {
+    /// The payload format version, `2.0` for all HTTP API events.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// The route key that matched the request, e.g. `GET /customer/{id}`.
+    #[serde(default)]
+    pub route_key: Option<String>,
+    /// The request path, including the leading '/'.
+    #[serde(default)]
+    pub raw_path: Option<String>,
+    /// The query string as sent by the caller, still url encoded.
+    #[serde(default)]
+    pub raw_query_string: Option<String>,
+    /// Any cookies sent with the request, these are not included in `headers`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub cookies: Vec<String>,
+    /// Request headers, multiple values for a single header are comma separated.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub headers: HashMap<String, String>,
+    /// Query parameters, multiple values for a single key are comma separated.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub query_string_parameters: HashMap<String, String>,
+    /// Path parameters defined on the matched route.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub path_parameters: HashMap<String, String>,
+    /// Stage variables for the deployed stage.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub stage_variables: HashMap<String, String>,
+    /// Details of the API, caller and authorizer.
+    #[serde(default)]
+    pub request_context: HttpApiRequestContext,
+    /// The request payload.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Whether the body is base64 encoded.
+    #[serde(default)]
+    pub is_base64_encoded: bool,
+}
+
+/// The `requestContext` of an HTTP API request.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiRequestContext {
+    /// The AWS account id owning the API.
+    #[serde(default)]
+    pub account_id: Option<String>,
+    /// The API id.
+    #[serde(default)]
+    pub api_id: Option<String>,
+    /// Authorizer output, keyed by authorizer type (e.g. `jwt` or `lambda`).
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub authorizer: HashMap<String, Value>,
+    /// The full domain name used to invoke the API.
+    #[serde(default)]
+    pub domain_name: Option<String>,
+    /// HTTP specific details of the request.
+    #[serde(default)]
+    pub http: HttpApiRequestContextHttp,
+    /// The request id.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// The route key that matched the request.
+    #[serde(default)]
+    pub route_key: Option<String>,
+    /// The deployed stage.
+    #[serde(default)]
+    pub stage: Option<String>,
+    /// Epoch time of the request in milliseconds.
+    #[serde(default)]
+    pub time_epoch: i64,
+}
+
+/// The `requestContext.http` of an HTTP API request.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiRequestContextHttp {
+    /// The http method, e.g. `GET`.
+    #[serde(default)]
+    pub method: Option<String>,
+    /// The request path, including the leading '/'.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The request protocol, e.g. `HTTP/1.1`.
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// The caller's source IP.
+    #[serde(default)]
+    pub source_ip: Option<String>,
+    /// The caller's user agent.
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+/// The API Gateway HTTP API response (payload format version 2.0).
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiResponse {
+    /// Http status code.
+    pub status_code: i64,
+    /// Response headers, multiple values for a single header are comma separated.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub headers: HashMap<String, String>,
+    /// Cookies to be set on the client, each as the value of a `Set-Cookie` header.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub cookies: Vec<String>,
+    /// Response body.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Whether the body is base64 encoded.
+    #[serde(default)]
+    pub is_base64_encoded: bool,
+}
+
+impl From<ApiGatewayProxyResponse> for HttpApiResponse {
+    fn from(response: ApiGatewayProxyResponse) -> Self {
+        let mut headers = response.headers;
+        let mut cookies = Vec::new();
+        for (k, v) in response.multi_value_headers {
+            if k.eq_ignore_ascii_case("Set-Cookie") {
+                cookies.extend(v);
+            } else {
+                headers.insert(k, v.join(", "));
+            }
+        }
+        HttpApiResponse {
+            status_code: response.status_code,
+            headers,
+            cookies,
+            body: response.body,
+            is_base64_encoded: response.is_base64_encoded.unwrap_or(false),
+        }
+    }
+}
+
+/// The response to an SQS, Kinesis or DynamoDB stream event reporting the records that failed,
+/// this requires `ReportBatchItemFailures` to be enabled on the event source mapping.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchResponse {
+    /// The records that failed and should be retried.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub batch_item_failures: Vec<BatchItemFailure>,
+}
+
+/// A single failed record within a `BatchResponse`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemFailure {
+    /// The SQS message id or the stream record sequence number.
+    pub item_identifier: String,
+}
+
+/// The DynamoDB Streams event.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct DynamoDbEvent {
+    /// The stream records, in the order of the changes to the table.
+    #[serde(rename = "Records", default, deserialize_with = "null_as_default")]
+    pub records: Vec<DynamoDbEventRecord>,
+}
+
+/// A single record of a DynamoDB Streams event.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamoDbEventRecord {
+    /// The event id.
+    #[serde(rename = "eventID", default)]
+    pub event_id: Option<String>,
+    /// The type of change, one of `INSERT`, `MODIFY` or `REMOVE`.
+    #[serde(default)]
+    pub event_name: Option<String>,
+    /// The ARN of the stream.
+    #[serde(rename = "eventSourceARN", default)]
+    pub event_source_arn: Option<String>,
+    /// The AWS region of the table.
+    #[serde(default)]
+    pub aws_region: Option<String>,
+    /// The change to the table.
+    #[serde(default)]
+    pub dynamodb: DynamoDbStreamRecord,
+}
+
+/// The `dynamodb` change within a DynamoDB Streams record, images are maps of attribute values in
+/// the DynamoDB wire format (e.g. `{"S": "value"}`).
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct DynamoDbStreamRecord {
+    /// Epoch time in seconds of the change.
+    #[serde(default)]
+    pub approximate_creation_date_time: f64,
+    /// The primary key attributes of the item.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub keys: HashMap<String, Value>,
+    /// The item after the change, if the stream view type includes new images.
+    #[serde(default)]
+    pub new_image: Option<HashMap<String, Value>>,
+    /// The item before the change, if the stream view type includes old images.
+    #[serde(default)]
+    pub old_image: Option<HashMap<String, Value>>,
+    /// The sequence number of the record within its' shard.
+    #[serde(default)]
+    pub sequence_number: Option<String>,
+    /// The size of the record in bytes.
+    #[serde(default)]
+    pub size_bytes: i64,
+    /// The stream view type, e.g. `NEW_AND_OLD_IMAGES`.
+    #[serde(default)]
+    pub stream_view_type: Option<String>,
+}
+
+/// The API Gateway WebSocket API request, used for the `$connect`, `$disconnect`, `$default` and
+/// custom routes.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketRequest {
+    /// Request headers, only sent with `$connect`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub headers: HashMap<String, String>,
+    /// Request headers with all values, only sent with `$connect`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub multi_value_headers: HashMap<String, Vec<String>>,
+    /// Query parameters with all values, only sent with `$connect`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub multi_value_query_string_parameters: HashMap<String, Vec<String>>,
+    /// Stage variables for the deployed stage.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub stage_variables: HashMap<String, String>,
+    /// Details of the API, connection and authorizer.
+    #[serde(default)]
+    pub request_context: WebSocketRequestContext,
+    /// The message payload.
+    #[serde(default)]
+    pub body: Option<String>,
+    /// Whether the body is base64 encoded.
+    #[serde(default)]
+    pub is_base64_encoded: bool,
+}
+
+/// The `requestContext` of a WebSocket API request.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketRequestContext {
+    /// The route key that matched the request, e.g. `$connect` or `sendMessage`.
+    #[serde(default)]
+    pub route_key: Option<String>,
+    /// The event type, one of `CONNECT`, `DISCONNECT` or `MESSAGE`.
+    #[serde(default)]
+    pub event_type: Option<String>,
+    /// The id of the connection, used to post messages back to the client.
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    /// Epoch time of the connection in milliseconds.
+    #[serde(default)]
+    pub connected_at: i64,
+    /// The domain name used to connect to the API.
+    #[serde(default)]
+    pub domain_name: Option<String>,
+    /// The deployed stage.
+    #[serde(default)]
+    pub stage: Option<String>,
+    /// The API id.
+    #[serde(default)]
+    pub api_id: Option<String>,
+    /// The request id.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// The message id, only sent with messages.
+    #[serde(default)]
+    pub message_id: Option<String>,
+    /// Authorizer output, set by the `$connect` authorizer for all routes.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub authorizer: HashMap<String, Value>,
+}
+
+/// The API Gateway Lambda authorizer request for both `TOKEN` and `REQUEST` authorizers, as sent by
+/// REST APIs and HTTP APIs using payload format version 1.0. The request fields are only sent to
+/// `REQUEST` authorizers.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorizerRequest {
+    /// The authorizer type, either `TOKEN` or `REQUEST`.
+    #[serde(rename = "type", default)]
+    pub authorizer_type: Option<String>,
+    /// The token from the configured token source (usually the `Authorization` header).
+    #[serde(default)]
+    pub authorization_token: Option<String>,
+    /// The ARN of the method being invoked, `arn:aws:execute-api:{region}:{account}:{api}/{stage}/{method}/{path}`.
+    #[serde(default)]
+    pub method_arn: Option<String>,
+    /// The request path.
+    #[serde(default)]
+    pub path: Option<String>,
+    /// The http method, e.g. `GET`.
+    #[serde(default)]
+    pub http_method: Option<String>,
+    /// Request headers.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub headers: HashMap<String, String>,
+    /// Request headers with all values.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub multi_value_headers: HashMap<String, Vec<String>>,
+    /// Query parameters.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub query_string_parameters: HashMap<String, String>,
+    /// Query parameters with all values.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub multi_value_query_string_parameters: HashMap<String, Vec<String>>,
+    /// Path parameters defined on the resource.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub path_parameters: HashMap<String, String>,
+    /// Stage variables for the deployed stage.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub stage_variables: HashMap<String, String>,
+}
+
+/// The API Gateway HTTP API Lambda authorizer request (payload format version 2.0), this is the
+/// HTTP API request with the route ARN and identity sources added.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiAuthorizerRequest {
+    /// The ARN of the route being invoked, `arn:aws:execute-api:{region}:{account}:{api}/{stage}/{method}/{path}`.
+    #[serde(default)]
+    pub route_arn: Option<String>,
+    /// The values of the configured identity sources.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub identity_source: Vec<String>,
+    /// The request being authorized.
+    #[serde(flatten)]
+    pub request: HttpApiRequest,
+}
+
+/// The simple response format of an HTTP API Lambda authorizer.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpApiSimpleAuthorizerResponse {
+    /// Whether the request is allowed.
+    pub is_authorized: bool,
+    /// Values made available to the integration as `$context.authorizer.*`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub context: HashMap<String, Value>,
+}
+
+/// The parts of an `ApiGatewayProxyRequest` that vary between requests, used to build proxy
+/// requests from other event types.
+pub(crate) struct ProxyRequestParts {
+    pub(crate) path: String,
+    pub(crate) http_method: Option<String>,
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) multi_value_headers: HashMap<String, Vec<String>>,
+    pub(crate) multi_value_query_string_parameters: HashMap<String, Vec<String>>,
+    pub(crate) authorizer: HashMap<String, Value>,
+    pub(crate) body: Option<String>,
+    pub(crate) is_base64_encoded: bool,
+}
+
+pub(crate) fn proxy_request(parts: ProxyRequestParts) -> ApiGatewayProxyRequest {
+    let mut path_parameters = HashMap::new();
+    path_parameters.insert("proxy".to_string(), parts.path.trim_start_matches('/').to_string());
+    ApiGatewayProxyRequest {
+        resource: Some("/{proxy+}".to_string()),
+        path: Some(parts.path),
+        http_method: parts.http_method.clone(),
+        headers: parts.headers,
+        multi_value_headers: parts.multi_value_headers,
+        query_string_parameters: HashMap::new(),
+        multi_value_query_string_parameters: parts.multi_value_query_string_parameters,
+        path_parameters,
+        stage_variables: HashMap::new(),
+        request_context: ApiGatewayProxyRequestContext {
+            account_id: None,
+            resource_id: None,
+            stage: None,
+            request_id: None,
+            identity: ApiGatewayRequestIdentity {
+                cognito_identity_pool_id: None,
+                account_id: None,
+                cognito_identity_id: None,
+                caller: None,
+                api_key: None,
+                access_key: None,
+                source_ip: None,
+                cognito_authentication_type: None,
+                cognito_authentication_provider: None,
+                user_arn: None,
+                user_agent: None,
+                user: None,
+            },
+            resource_path: Some("/{proxy+}".to_string()),
+            authorizer: parts.authorizer,
+            http_method: parts.http_method,
+            apiid: None,
+        },
+        body: parts.body,
+        is_base64_encoded: Some(parts.is_base64_encoded),
+    }
+}
+
+pub(crate) fn http_api_proxy_request(event: HttpApiRequest) -> ApiGatewayProxyRequest {
+    let path = event.raw_path
+        .or(event.request_context.http.path)
+        .unwrap_or_default();
+    let mut headers = event.headers;
+    if !event.cookies.is_empty() {
+        headers.insert("cookie".to_string(), event.cookies.join("; "));
+    }
+    let multi_value_query_string_parameters = match event.raw_query_string {
+        Some(raw_query_string) => parse_url_encoded(&raw_query_string),
+        None => HashMap::new(),
+    };
+    let mut authorizer = HashMap::new();
+    if let Some(claims) = event.request_context.authorizer.get("jwt").map(|jwt| &jwt["claims"]).filter(|claims| claims.is_object()) {
+        authorizer.insert("claims".to_string(), claims.clone());
+    }
+    if let Some(iam) = event.request_context.authorizer.get("iam") {
+        authorizer.insert("iam".to_string(), iam.clone());
+    }
+    let mut request = proxy_request(ProxyRequestParts {
+        path,
+        http_method: event.request_context.http.method,
+        headers,
+        multi_value_headers: HashMap::new(),
+        multi_value_query_string_parameters,
+        authorizer,
+        body: event.body,
+        is_base64_encoded: event.is_base64_encoded,
+    });
+    request.path_parameters.extend(event.path_parameters);
+    request
+}
+
+pub(crate) fn alb_proxy_request(event: AlbTargetGroupRequest) -> ApiGatewayProxyRequest {
+    let path = event.path.unwrap_or_default();
+    let decode = |parameters: HashMap<String, Vec<String>>| -> HashMap<String, Vec<String>> {
+        parameters.into_iter()
+            .map(|(k, v)| (percent_decode(&k, true), v.iter().map(|v| percent_decode(v, true)).collect()))
+            .collect()
+    };
+    let mut multi_value_query_string_parameters = decode(event.multi_value_query_string_parameters);
+    for (k, v) in event.query_string_parameters {
+        multi_value_query_string_parameters.insert(percent_decode(&k, true), vec![percent_decode(&v, true)]);
+    }
+    proxy_request(ProxyRequestParts {
+        path,
+        http_method: event.http_method,
+        headers: event.headers,
+        multi_value_headers: event.multi_value_headers,
+        multi_value_query_string_parameters,
+        authorizer: HashMap::new(),
+        body: event.body,
+        is_base64_encoded: event.is_base64_encoded,
+    })
+}
+
+/// Converts the response for an ALB target group, using multi-value headers only if the target group
+/// has them enabled. Without multi-value headers only the last `Set-Cookie` header can be returned.
+pub(crate) fn alb_response(response: ApiGatewayProxyResponse, multi_value_headers: bool) -> AlbTargetGroupResponse {
+    let mut headers = response.headers;
+    let mut multi_value = response.multi_value_headers;
+    if multi_value_headers {
+        for (k, v) in headers.drain() {
+            multi_value.entry(k).or_default().push(v);
+        }
+    } else {
+        for (k, v) in multi_value.drain() {
+            if k.eq_ignore_ascii_case("Set-Cookie") {
+                if v.len() > 1 {
+                    warn!("{} of {} cookies dropped, enable multi-value headers on the target group to return them all",
+                          v.len() - 1, v.len());
+                }
+                if let Some(cookie) = v.into_iter().last() {
+                    headers.insert(k, cookie);
+                }
+            } else {
+                headers.insert(k, v.join(", "));
+            }
+        }
+    }
+    AlbTargetGroupResponse {
+        status_code: response.status_code,
+        status_description: Some(status_description(response.status_code)),
+        headers,
+        multi_value_headers: multi_value,
+        body: response.body,
+        is_base64_encoded: response.is_base64_encoded.unwrap_or(false),
+    }
+}
+
+fn status_description(status_code: i64) -> String {
+    let reason = match status_code {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        301 => "Moved Permanently",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        415 => "Unsupported Media Type",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => return status_code.to_string(),
+    };
+    format!("{} {}", status_code, reason)
+}
+
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where D: Deserializer<'de>,
+          T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod events_tests {
+    use crate::request::SrvrlsRequest;
+
+    use super::*;
+
+    #[test]
+    fn test_http_api_request() {
+        let event = r#"{
+            "version": "2.0",
+            "routeKey": "GET /customer/{id}",
+            "rawPath": "/customer/CUST-A23948",
+            "rawQueryString": "expand=true",
+            "cookies": ["session=abc"],
+            "headers": {"accept": "application/json"},
+            "queryStringParameters": {"expand": "true"},
+            "pathParameters": null,
+            "requestContext": {
+                "http": {"method": "GET", "path": "/customer/CUST-A23948"},
+                "authorizer": {"jwt": {"claims": {"sub": "user-1"}, "scopes": null}}
+            },
+            "isBase64Encoded": false
+        }"#;
+        let request: HttpApiRequest = serde_json::from_str(event).unwrap();
+
+        assert_eq!(Some("/customer/CUST-A23948".to_string()), request.raw_path);
+        assert_eq!(vec!["session=abc".to_string()], request.cookies);
+        assert!(request.path_parameters.is_empty());
+        assert_eq!(Some("GET".to_string()), request.request_context.http.method);
+        assert_eq!(None, request.body);
+    }
+
+    #[test]
+    fn test_alb_response() {
+        let mut response = ApiGatewayProxyResponse {
+            status_code: 200,
+            headers: HashMap::new(),
+            multi_value_headers: HashMap::new(),
+            body: None,
+            is_base64_encoded: None,
+        };
+        response.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        response.multi_value_headers.insert("Set-Cookie".to_string(), vec!["session=abc".to_string(), "cart=".to_string()]);
+        response.multi_value_headers.insert("Vary".to_string(), vec!["Accept".to_string(), "Origin".to_string()]);
+
+        let alb = alb_response(response.clone(), false);
+        assert_eq!("cart=", alb.headers["Set-Cookie"]);
+        assert_eq!("Accept, Origin", alb.headers["Vary"]);
+        assert!(alb.multi_value_headers.is_empty());
+        assert_eq!(Some("200 OK".to_string()), alb.status_description);
+
+        let alb = alb_response(response, true);
+        assert_eq!(vec!["session=abc".to_string(), "cart=".to_string()], alb.multi_value_headers["Set-Cookie"]);
+        assert_eq!(vec!["application/json".to_string()], alb.multi_value_headers["Content-Type"]);
+        assert!(alb.headers.is_empty());
+    }
+
+    #[test]
+    fn test_http_api_proxy_request() {
+        let event = r#"{
+            "version": "2.0",
+            "routeKey": "GET /customer/{id}",
+            "rawPath": "/customer/CUST-A23948",
+            "pathParameters": {"id": "CUST-A23948"},
+            "requestContext": {
+                "http": {"method": "GET"},
+                "authorizer": {"jwt": {"scopes": ["orders"]}}
+            }
+        }"#;
+        let request: SrvrlsRequest = http_api_proxy_request(serde_json::from_str(event).unwrap()).into();
+
+        assert_eq!("CUST-A23948", request.path_param::<String, _>("id").unwrap());
+        assert_eq!("customer/CUST-A23948", request.path);
+        assert_eq!("", request.authentication_claim("sub"));
+    }
+}
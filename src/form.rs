@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use crate::components::{parse_url_encoded, split_header_value, SrvrlsError};
+
+/// Size limits applied when parsing form and multipart request bodies, exceeding any of these
+/// results in a `SrvrlsError::PayloadTooLarge` (413) error.
+/// ```rust
+/// # use srvrls::form::FormLimits;
+/// let limits = FormLimits {
+///     max_part_size: 1024 * 1024,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormLimits {
+    /// The maximum size in bytes of the full (decoded) request body, defaults to the 6 MB
+    /// AWS Lambda payload limit.
+    pub max_body_size: usize,
+    /// The maximum size in bytes of the content of any single multipart part.
+    pub max_part_size: usize,
+    /// The maximum number of multipart parts.
+    pub max_parts: usize,
+}
+
+impl Default for FormLimits {
+    fn default() -> Self {
+        FormLimits {
+            max_body_size: 6 * 1024 * 1024,
+            max_part_size: 6 * 1024 * 1024,
+            max_parts: 100,
+        }
+    }
+}
+
+/// A single part of a `multipart/form-data` request body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartPart {
+    /// The name of the form field.
+    pub name: String,
+    /// The filename, if the part is a file upload.
+    pub filename: Option<String>,
+    /// The content type of the part, if provided.
+    pub content_type: Option<String>,
+    /// The raw content of the part.
+    pub content: Vec<u8>,
+}
+
+impl MultipartPart {
+    /// Provides the content as a String, any invalid UTF-8 is replaced.
+    #[must_use]
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).into_owned()
+    }
+}
+
+pub(crate) fn parse_form(body: &[u8], limits: &FormLimits) -> Result<HashMap<String, Vec<String>>, SrvrlsError> {
+    if body.len() > limits.max_body_size {
+        return Err(SrvrlsError::PayloadTooLarge);
+    }
+    Ok(parse_url_encoded(&String::from_utf8_lossy(body)))
+}
+
+pub(crate) fn parse_multipart(content_type: &str, body: &[u8], limits: &FormLimits) -> Result<Vec<MultipartPart>, SrvrlsError> {
+    if body.len() > limits.max_body_size {
+        return Err(SrvrlsError::PayloadTooLarge);
+    }
+    let (media_type, parameters) = split_header_value(content_type);
+    if !media_type.eq_ignore_ascii_case("multipart/form-data") {
+        return Err(bad_request("expected a multipart/form-data request"));
+    }
+    let boundary = match parameters.get("boundary") {
+        Some(boundary) if !boundary.is_empty() => boundary,
+        _ => return Err(bad_request("multipart boundary is missing")),
+    };
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut position = find(body, &delimiter, 0).ok_or_else(|| bad_request("multipart body is malformed"))?;
+    let mut parts = Vec::new();
+    loop {
+        position += delimiter.len();
+        if body[position..].starts_with(b"--") {
+            return Ok(parts);
+        }
+        if !body[position..].starts_with(b"\r\n") {
+            return Err(bad_request("multipart body is malformed"));
+        }
+        position += 2;
+        let header_end = find(body, b"\r\n\r\n", position).ok_or_else(|| bad_request("multipart part headers are malformed"))?;
+        let headers = String::from_utf8_lossy(&body[position..header_end]).into_owned();
+        let content_start = header_end + 4;
+        let mut closing_delimiter = b"\r\n".to_vec();
+        closing_delimiter.extend_from_slice(&delimiter);
+        let content_end = find(body, &closing_delimiter, content_start).ok_or_else(|| bad_request("multipart body is not terminated"))?;
+        if content_end - content_start > limits.max_part_size || parts.len() == limits.max_parts {
+            return Err(SrvrlsError::PayloadTooLarge);
+        }
+        parts.push(multipart_part(&headers, body[content_start..content_end].to_vec())?);
+        position = content_end + 2;
+    }
+}
+
+fn multipart_part(headers: &str, content: Vec<u8>) -> Result<MultipartPart, SrvrlsError> {
+    let mut name = None;
+    let mut filename = None;
+    let mut content_type = None;
+    for header in headers.split("\r\n") {
+        let mut header = header.splitn(2, ':');
+        let header_name = header.next().unwrap_or("").trim();
+        let header_value = header.next().unwrap_or("").trim();
+        if header_name.eq_ignore_ascii_case("Content-Disposition") {
+            let (_, mut parameters) = split_header_value(header_value);
+            name = parameters.remove("name");
+            filename = parameters.remove("filename");
+        } else if header_name.eq_ignore_ascii_case("Content-Type") {
+            content_type = Some(header_value.to_string());
+        }
+    }
+    match name {
+        None => Err(bad_request("multipart part is missing a name")),
+        Some(name) => Ok(MultipartPart { name, filename, content_type, content }),
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from > haystack.len() {
+        return None;
+    }
+    haystack[from..].windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| position + from)
+}
+
+fn bad_request(message: &str) -> SrvrlsError {
+    SrvrlsError::BadRequestWithSimpleMessage(message.to_string())
+}
+
+#[cfg(test)]
+mod form_tests {
+    use super::*;
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=----srvrls7MA4YWxk";
+
+    fn multipart_body() -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(b"------srvrls7MA4YWxk\r\n");
+        body.extend_from_slice(b"Content-Disposition: form-data; name=\"description\"\r\n\r\n");
+        body.extend_from_slice(b"quarterly report\r\n");
+        body.extend_from_slice(b"------srvrls7MA4YWxk\r\n");
+        body.extend_from_slice(b"Content-Disposition: form-data; name=\"file\"; filename=\"report; q1.bin\"\r\n");
+        body.extend_from_slice(b"Content-Type: application/octet-stream\r\n\r\n");
+        body.extend_from_slice(&[0, 159, 146, 150, 13, 10]);
+        body.extend_from_slice(b"\r\n------srvrls7MA4YWxk--\r\n");
+        body
+    }
+
+    #[test]
+    fn test_parse_form() {
+        let form = parse_form(b"Body=Hello+there&MediaUrl=a&MediaUrl=b", &FormLimits::default()).unwrap();
+        assert_eq!(vec!["Hello there".to_string()], form["Body"]);
+        assert_eq!(vec!["a".to_string(), "b".to_string()], form["MediaUrl"]);
+    }
+
+    #[test]
+    fn test_parse_form_too_large() {
+        let limits = FormLimits { max_body_size: 4, ..Default::default() };
+        assert_eq!(SrvrlsError::PayloadTooLarge, parse_form(b"Body=Hello", &limits).unwrap_err());
+    }
+
+    #[test]
+    fn test_parse_multipart() {
+        let parts = parse_multipart(CONTENT_TYPE, &multipart_body(), &FormLimits::default()).unwrap();
+
+        assert_eq!(2, parts.len());
+        assert_eq!("description", parts[0].name);
+        assert_eq!(None, parts[0].filename);
+        assert_eq!("quarterly report", parts[0].text());
+        assert_eq!("file", parts[1].name);
+        assert_eq!(Some("report; q1.bin".to_string()), parts[1].filename);
+        assert_eq!(Some("application/octet-stream".to_string()), parts[1].content_type);
+        assert_eq!(vec![0, 159, 146, 150, 13, 10], parts[1].content);
+    }
+
+    #[test]
+    fn test_parse_multipart_limits() {
+        let limits = FormLimits { max_part_size: 5, ..Default::default() };
+        assert_eq!(SrvrlsError::PayloadTooLarge, parse_multipart(CONTENT_TYPE, &multipart_body(), &limits).unwrap_err());
+
+        let limits = FormLimits { max_parts: 1, ..Default::default() };
+        assert_eq!(SrvrlsError::PayloadTooLarge, parse_multipart(CONTENT_TYPE, &multipart_body(), &limits).unwrap_err());
+    }
+
+    #[test]
+    fn test_parse_multipart_malformed() {
+        let result = parse_multipart("multipart/form-data", &multipart_body(), &FormLimits::default());
+        assert_eq!(SrvrlsError::BadRequestWithSimpleMessage("multipart boundary is missing".to_string()), result.unwrap_err());
+
+        let result = parse_multipart(CONTENT_TYPE, b"------srvrls7MA4YWxk\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nb", &FormLimits::default());
+        assert_eq!(SrvrlsError::BadRequestWithSimpleMessage("multipart body is not terminated".to_string()), result.unwrap_err());
+    }
+}
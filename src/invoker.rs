@@ -0,0 +1,209 @@
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::{Context, Handler};
+use serde_json::Value;
+
+use crate::application::{Srvrls, SrvrlsApplication};
+use crate::dispatcher::EventSource;
+use crate::events::{alb_proxy_request, http_api_proxy_request};
+
+/// Replays recorded events through a `Srvrls` instance outside of AWS Lambda, this is useful for
+/// running production events locally and diffing the responses.
+///
+/// Event files may hold a single event, a JSON array of events or one event per line (JSON Lines).
+/// API Gateway REST (v1), HTTP API (v2), Function URL and ALB events are supported. As for an ALB
+/// target group, only the last `Set-Cookie` header is returned for ALB events without
+/// multi-value headers.
+/// ```rust
+/// # use srvrls::application::{Srvrls, SrvrlsApplication};
+/// # use srvrls::components::SrvrlsError;
+/// # use srvrls::invoker::SrvrlsInvoker;
+/// # use srvrls::request::SrvrlsRequest;
+/// # use srvrls::response::SrvrlsResponse;
+/// # struct App {}
+/// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+/// #         Ok(SrvrlsResponse::ok_empty())
+/// #     }
+/// # }
+/// let mut invoker = SrvrlsInvoker::new(Srvrls::new(App {}));
+/// let responses = invoker.invoke_file("test_data/happy_path_event.json").unwrap();
+/// assert_eq!(200, responses[0].status_code);
+/// ```
+/// A small binary in your project can then replay any number of event files, e.g.,
+/// `cargo run --bin invoke -- test_data/*.json`
+/// ```ignore
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let srvrls = build_srvrls();
+///     SrvrlsInvoker::new(srvrls).invoke_from_args()
+/// }
+/// ```
+pub struct SrvrlsInvoker<T: SrvrlsApplication> {
+    srvrls: Srvrls<T>,
+}
+
+impl<T: SrvrlsApplication> SrvrlsInvoker<T> {
+    /// Create a new invoker for the provided `Srvrls` instance.
+    pub fn new(srvrls: Srvrls<T>) -> Self {
+        SrvrlsInvoker { srvrls }
+    }
+
+    /// Runs a single event through the application and returns the response.
+    ///
+    /// # Errors
+    /// Returns an error if the event is not a supported API Gateway or ALB event.
+    pub fn invoke_event(&mut self, event: Value) -> Result<ApiGatewayProxyResponse, Box<dyn Error>> {
+        let request = match EventSource::detect(&event) {
+            Some(EventSource::Alb) => alb_proxy_request(serde_json::from_value(event)?),
+            Some(EventSource::HttpApi) | Some(EventSource::FunctionUrl) => http_api_proxy_request(serde_json::from_value(event)?),
+            _ => serde_json::from_value(event)?,
+        };
+        Ok(self.srvrls.run(request, Context::default())?)
+    }
+
+    /// Runs all events found within the provided string, this may be a single event, an array of
+    /// events or a series of events such as JSON Lines.
+    ///
+    /// # Errors
+    /// Returns an error if the contents are not valid JSON or any event is not supported.
+    pub fn invoke_str(&mut self, contents: &str) -> Result<Vec<ApiGatewayProxyResponse>, Box<dyn Error>> {
+        let mut responses = Vec::new();
+        for value in serde_json::Deserializer::from_str(contents).into_iter::<Value>() {
+            match value? {
+                Value::Array(events) => {
+                    for event in events {
+                        responses.push(self.invoke_event(event)?);
+                    }
+                }
+                event => responses.push(self.invoke_event(event)?),
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Runs all events found within the file at the provided path.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or its' contents are not supported events.
+    pub fn invoke_file<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<ApiGatewayProxyResponse>, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        self.invoke_str(&contents)
+    }
+
+    /// Runs all events within the provided files and writes each response as a line of JSON.
+    ///
+    /// # Errors
+    /// Returns an error if any file cannot be read or the output cannot be written.
+    pub fn invoke_files<P: AsRef<Path>, W: Write>(&mut self, paths: &[P], out: &mut W) -> Result<(), Box<dyn Error>> {
+        for path in paths {
+            for response in self.invoke_file(path)? {
+                writeln!(out, "{}", serde_json::to_value(&response)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs all event files named in the command line arguments, printing the responses to stdout.
+    ///
+    /// # Errors
+    /// Returns an error if any file cannot be read or holds unsupported events.
+    pub fn invoke_from_args(&mut self) -> Result<(), Box<dyn Error>> {
+        let paths: Vec<String> = std::env::args().skip(1).collect();
+        let stdout = std::io::stdout();
+        self.invoke_files(&paths, &mut stdout.lock())
+    }
+}
+
+#[cfg(test)]
+mod invoker_tests {
+    use super::*;
+    use crate::components::SrvrlsError;
+    use crate::request::SrvrlsRequest;
+    use crate::response::SrvrlsResponse;
+    use serde_json::json;
+
+    struct EchoApplication {}
+
+    impl SrvrlsApplication for EchoApplication {
+        fn handle(&mut self, event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+            match event.path_parameter(0).as_str() {
+                "target" => Ok(SrvrlsResponse::ok(json!({
+                    "path": event.path,
+                    "query": event.query_parameter("q"),
+                    "sub": event.authentication_claim("sub"),
+                }))),
+                _ => Err(SrvrlsError::NotFound)
+            }
+        }
+    }
+
+    fn invoker() -> SrvrlsInvoker<EchoApplication> {
+        SrvrlsInvoker::new(Srvrls::new(EchoApplication {}))
+    }
+
+    #[test]
+    fn test_invoke_file() {
+        let responses = invoker().invoke_file("test_data/happy_path_event.json").unwrap();
+        assert_eq!(1, responses.len());
+        assert_eq!(200, responses[0].status_code);
+
+        let responses = invoker().invoke_file("test_data/not_supported_event.json").unwrap();
+        assert_eq!(404, responses[0].status_code);
+    }
+
+    #[test]
+    fn test_invoke_json_lines() {
+        let event = fs::read_to_string("test_data/happy_path_event.json").unwrap();
+        let event: Value = serde_json::from_str(&event).unwrap();
+        let contents = format!("{}\n{}\n", event, event);
+        assert_eq!(2, invoker().invoke_str(&contents).unwrap().len());
+
+        let contents = format!("[{},{}]", event, event);
+        assert_eq!(2, invoker().invoke_str(&contents).unwrap().len());
+    }
+
+    #[test]
+    fn test_invoke_http_api() {
+        let event = json!({
+            "version": "2.0",
+            "rawPath": "/target/id",
+            "rawQueryString": "q=a%20b&q=c",
+            "requestContext": {
+                "http": {"method": "GET"},
+                "authorizer": {"jwt": {"claims": {"sub": "user-1"}}}
+            },
+            "isBase64Encoded": false
+        });
+        let response = invoker().invoke_event(event).unwrap();
+        assert_eq!(Some(r#"{"path":"target/id","query":["a b","c"],"sub":"user-1"}"#.to_string()), response.body);
+    }
+
+    #[test]
+    fn test_invoke_alb() {
+        let event = json!({
+            "httpMethod": "GET",
+            "path": "/target/id",
+            "queryStringParameters": {"q": "a%20b"},
+            "headers": {},
+            "requestContext": {"elb": {"targetGroupArn": "arn:aws:elasticloadbalancing:us-west-2:123456789012:targetgroup/srvrls/1"}},
+            "isBase64Encoded": false,
+            "body": ""
+        });
+        let response = invoker().invoke_event(event).unwrap();
+        assert_eq!(Some(r#"{"path":"target/id","query":["a b"],"sub":""}"#.to_string()), response.body);
+    }
+
+    #[test]
+    fn test_invoke_files() {
+        let mut out = Vec::new();
+        invoker().invoke_files(&["test_data/happy_path_event.json", "test_data/not_supported_event.json"], &mut out).unwrap();
+        let lines: Vec<Value> = String::from_utf8(out).unwrap().lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(200, lines[0]["statusCode"]);
+        assert_eq!(404, lines[1]["statusCode"]);
+    }
+}
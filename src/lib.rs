@@ -14,7 +14,7 @@
 //! - reduce needed boilerplate in serverless applications
 //! - provide opinionated defaults to otherwise open questions (more on this later)
 //! - provide decoupling between the serverless function provider and the application logic
-//! (keeping open the option of supporting Google or Azure functions in the future)
+//!   (keeping open the option of supporting Google or Azure functions in the future)
 //!
 
 /// Application provides the AWS Lambda wrapper and response handling.
@@ -28,3 +28,61 @@ pub mod response;
 
 /// Request provides a simplified input request struct with opinionated getter methods.
 pub mod request;
+
+/// Events holds the AWS Lambda event payloads that are not provided by `aws_lambda_events`.
+pub mod events;
+
+/// Invoker replays recorded events through an application outside of AWS Lambda.
+pub mod invoker;
+
+/// Testing provides a request builder and response assertions for testing applications.
+pub mod testing;
+
+/// Form provides parsing of url encoded and multipart request bodies.
+pub mod form;
+
+/// Serializer provides the response serializers used for content negotiation.
+pub mod serializer;
+
+/// Compression provides the configuration for compressing response bodies, this requires the
+/// `compression` feature.
+#[cfg(feature = "compression")]
+pub mod compression;
+
+/// Cookie provides the response `Cookie` builder and request cookie parsing.
+pub mod cookie;
+
+/// Queue provides the SQS batch handler with partial batch failure reporting.
+pub mod queue;
+
+/// Notification provides the SNS notification handler.
+pub mod notification;
+
+/// EventBridge provides the EventBridge (CloudWatch Events) dispatcher.
+pub mod eventbridge;
+
+/// Storage provides the S3 object event handler.
+pub mod storage;
+
+/// Stream provides the Kinesis and DynamoDB Streams record handlers.
+pub mod stream;
+
+/// WebSocket provides the API Gateway WebSocket API handler and outbound connection posting.
+pub mod websocket;
+
+/// Authorizer provides the API Gateway Lambda authorizer handler.
+pub mod authorizer;
+
+/// Cognito provides the Cognito user pool trigger handler.
+pub mod cognito;
+
+/// Dispatcher provides a single entry point for events from any supported source.
+pub mod dispatcher;
+
+/// WarmUp provides the configuration of warm-up (keep-alive) pings.
+pub mod warmup;
+
+/// Azure provides the Azure Functions custom handler adapter.
+pub mod azure;
+
+mod query;
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::sns::{SnsEvent, SnsEventRecord};
+use aws_lambda_events::event::sqs::{SqsEvent, SqsMessage};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::{error, warn};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::components::SrvrlsEventError;
+use crate::events::BatchResponse;
+use crate::queue::process_batch;
+
+/// A single SNS notification with its' message deserialized from JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification<M> {
+    /// The SNS message id.
+    pub message_id: String,
+    /// The deserialized message.
+    pub message: M,
+    /// The subject of the notification, or an empty string if none was provided.
+    pub subject: String,
+    /// The string values of the message attributes set by the publisher.
+    pub message_attributes: HashMap<String, String>,
+    /// The ARN of the topic the notification was published to, this is empty for notifications
+    /// delivered to SQS with raw message delivery.
+    pub topic_arn: String,
+}
+
+impl<M> Notification<M> {
+    /// Provides the value of a message attribute, or an empty string if it is missing.
+    #[must_use]
+    pub fn message_attribute(&self, name: &str) -> String {
+        match self.message_attributes.get(name) {
+            None => "".to_string(),
+            Some(value) => value.clone(),
+        }
+    }
+}
+
+/// This trait should be implemented by your application to handle SNS notifications, whether
+/// delivered directly to AWS Lambda or through an SQS queue subscribed to the topic.
+///
+/// Messages are deserialized from JSON, a message that is not valid JSON is deserialized from the
+/// plain string so that `type Message = String` accepts any message.
+/// ```rust
+/// # use serde::Deserialize;
+/// # use srvrls::components::SrvrlsEventError;
+/// # use srvrls::notification::{Notification, SrvrlsNotificationApplication};
+/// #[derive(Deserialize)]
+/// struct PriceChanged {
+///     sku: String,
+///     price: i64,
+/// }
+///
+/// struct PriceApplication {}
+///
+/// impl SrvrlsNotificationApplication for PriceApplication {
+///     type Message = PriceChanged;
+///
+///     fn handle(&mut self, notification: Notification<PriceChanged>) -> Result<(), SrvrlsEventError> {
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait SrvrlsNotificationApplication {
+    /// The type each message is deserialized into.
+    type Message: DeserializeOwned;
+
+    /// This method receives a single notification.
+    ///
+    /// # Errors
+    /// A `SrvrlsEventError::Retryable` error causes the notification to be delivered again, a
+    /// `SrvrlsEventError::NonRetryable` error is logged and the notification is dropped.
+    fn handle(&mut self, notification: Notification<Self::Message>) -> Result<(), SrvrlsEventError>;
+}
+
+/// `SrvrlsNotification` wraps your application that implements `SrvrlsNotificationApplication` and
+/// interfaces with AWS Lambda for SNS events, see `SrvrlsSnsOverSqs` for SQS events from a queue
+/// subscribed to a topic.
+///
+/// A retryable error fails the invocation so that AWS Lambda retries the (asynchronous) delivery.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let notification = SrvrlsNotification::new(PriceApplication {});
+///     lambda!(notification);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsNotification<T: SrvrlsNotificationApplication> {
+    application: T,
+}
+
+/// `SrvrlsSnsOverSqs` wraps your application that implements `SrvrlsNotificationApplication` and
+/// interfaces with AWS Lambda for SQS events from a queue subscribed to an SNS topic.
+///
+/// The SNS envelope is unwrapped, or if raw message delivery is enabled the message attributes are
+/// taken from the SQS message, and failed messages are reported as in `SrvrlsQueue`.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let notification = SrvrlsSnsOverSqs::new(PriceApplication {});
+///     lambda!(notification);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsSnsOverSqs<T: SrvrlsNotificationApplication> {
+    notification: SrvrlsNotification<T>,
+}
+
+/// The envelope of an SNS notification delivered to SQS without raw message delivery.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SnsEnvelope {
+    #[serde(rename = "Type")]
+    type_: String,
+    message_id: String,
+    topic_arn: String,
+    #[serde(default)]
+    subject: Option<String>,
+    message: String,
+    #[serde(default)]
+    message_attributes: HashMap<String, Value>,
+}
+
+impl<T: SrvrlsNotificationApplication> SrvrlsNotification<T> {
+    /// Create a new `SrvrlsNotification` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsNotification { application }
+    }
+
+    fn handle_record(&mut self, record: SnsEventRecord) -> Result<(), SrvrlsEventError> {
+        let sns = record.sns;
+        self.handle_notification(Notification {
+            message_id: sns.message_id.unwrap_or_default(),
+            message: sns.message.unwrap_or_default(),
+            subject: sns.subject.unwrap_or_default(),
+            message_attributes: attribute_values(sns.message_attributes),
+            topic_arn: sns.topic_arn.unwrap_or_default(),
+        })
+    }
+
+    fn handle_queue_message(&mut self, message: SqsMessage) -> Result<(), SrvrlsEventError> {
+        let body = message.body.unwrap_or_default();
+        match serde_json::from_str::<SnsEnvelope>(&body) {
+            Ok(envelope) if envelope.type_ == "Notification" => self.handle_notification(Notification {
+                message_id: envelope.message_id,
+                message: envelope.message,
+                subject: envelope.subject.unwrap_or_default(),
+                message_attributes: attribute_values(envelope.message_attributes),
+                topic_arn: envelope.topic_arn,
+            }),
+            _ => {
+                let message_attributes = message.message_attributes.into_iter()
+                    .filter_map(|(name, attribute)| attribute.string_value.map(|value| (name, value)))
+                    .collect();
+                self.handle_notification(Notification {
+                    message_id: message.message_id.unwrap_or_default(),
+                    message: body,
+                    subject: "".to_string(),
+                    message_attributes,
+                    topic_arn: "".to_string(),
+                })
+            }
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification<String>) -> Result<(), SrvrlsEventError> {
+        let raw_message = notification.message;
+        let message = serde_json::from_str(&raw_message)
+            .or_else(|_| serde_json::from_value(Value::String(raw_message)))
+            .map_err(|e| SrvrlsEventError::NonRetryable(format!("unable to deserialize message: {}", e)))?;
+        self.application.handle(Notification {
+            message_id: notification.message_id,
+            message,
+            subject: notification.subject,
+            message_attributes: notification.message_attributes,
+            topic_arn: notification.topic_arn,
+        })
+    }
+}
+
+/// SNS message attributes are delivered as `{"Type": "String", "Value": "..."}`.
+fn attribute_values(attributes: HashMap<String, Value>) -> HashMap<String, String> {
+    attributes.into_iter()
+        .filter_map(|(name, attribute)| match &attribute["Value"] {
+            Value::String(value) => Some((name, value.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+impl<T: SrvrlsNotificationApplication> Handler<SnsEvent, (), HandlerError> for SrvrlsNotification<T> {
+    fn run(&mut self, event: SnsEvent, _ctx: Context) -> Result<(), HandlerError> {
+        for record in event.records {
+            let message_id = record.sns.message_id.clone().unwrap_or_default();
+            match self.handle_record(record) {
+                Ok(()) => {}
+                Err(SrvrlsEventError::Retryable(msg)) => {
+                    warn!("notification {} failed and will be retried: {}", message_id, msg);
+                    return Err(HandlerError::from(msg.as_str()));
+                }
+                Err(SrvrlsEventError::NonRetryable(msg)) => {
+                    error!("notification {} failed and will not be retried: {}", message_id, msg);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: SrvrlsNotificationApplication> SrvrlsSnsOverSqs<T> {
+    /// Create a new `SrvrlsSnsOverSqs` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsSnsOverSqs { notification: SrvrlsNotification::new(application) }
+    }
+}
+
+impl<T: SrvrlsNotificationApplication> Handler<SqsEvent, BatchResponse, HandlerError> for SrvrlsSnsOverSqs<T> {
+    fn run(&mut self, event: SqsEvent, _ctx: Context) -> Result<BatchResponse, HandlerError> {
+        let notification = &mut self.notification;
+        Ok(process_batch(event.records, |message| notification.handle_queue_message(message)))
+    }
+}
+
+#[cfg(test)]
+mod notification_tests {
+    use serde_json::json;
+
+    use crate::events::BatchItemFailure;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct PriceChanged {
+        sku: String,
+        price: i64,
+    }
+
+    struct PriceApplication {
+        received: Vec<(String, i64, String, String, String)>,
+    }
+
+    impl SrvrlsNotificationApplication for PriceApplication {
+        type Message = PriceChanged;
+
+        fn handle(&mut self, notification: Notification<PriceChanged>) -> Result<(), SrvrlsEventError> {
+            if notification.message.price == 0 {
+                return Err(SrvrlsEventError::Retryable("catalog unavailable".to_string()));
+            }
+            let region = notification.message_attribute("region");
+            self.received.push((notification.message.sku, notification.message.price, notification.subject, region, notification.topic_arn));
+            Ok(())
+        }
+    }
+
+    fn application() -> SrvrlsNotification<PriceApplication> {
+        SrvrlsNotification::new(PriceApplication { received: Vec::new() })
+    }
+
+    fn sns_over_sqs() -> SrvrlsSnsOverSqs<PriceApplication> {
+        SrvrlsSnsOverSqs::new(PriceApplication { received: Vec::new() })
+    }
+
+    fn sns_event(message: &str) -> SnsEvent {
+        serde_json::from_value(json!({"Records": [{
+            "EventVersion": "1.0",
+            "EventSubscriptionArn": "arn:aws:sns:us-east-2:123456789012:prices:c9135db0",
+            "EventSource": "aws:sns",
+            "Sns": {
+                "Type": "Notification",
+                "MessageId": "95df01b4-ee98-5cb9-9903-4c221d41eb5e",
+                "TopicArn": "arn:aws:sns:us-east-2:123456789012:prices",
+                "Subject": "price",
+                "Message": message,
+                "Timestamp": "2020-01-02T12:45:07.000Z",
+                "MessageAttributes": {"region": {"Type": "String", "Value": "eu"}}
+            }
+        }]})).unwrap()
+    }
+
+    fn sqs_event(body: &str) -> SqsEvent {
+        serde_json::from_value(json!({"Records": [{
+            "messageId": "msg-1",
+            "body": body,
+            "messageAttributes": {
+                "region": {"stringValue": "us", "stringListValues": [], "binaryListValues": [], "dataType": "String"}
+            },
+            "eventSourceARN": "arn:aws:sqs:us-east-2:123456789012:prices"
+        }]})).unwrap()
+    }
+
+    #[test]
+    fn test_sns_event() {
+        let mut notification = application();
+        notification.run(sns_event(r#"{"sku":"SKU-1","price":499}"#), Context::default()).unwrap();
+        assert_eq!(vec![("SKU-1".to_string(), 499, "price".to_string(), "eu".to_string(), "arn:aws:sns:us-east-2:123456789012:prices".to_string())],
+                   notification.application.received);
+
+        let result = notification.run(sns_event(r#"{"sku":"SKU-1","price":0}"#), Context::default());
+        assert!(result.is_err());
+        let result = notification.run(sns_event("not a price"), Context::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_sqs_envelope() {
+        let envelope = json!({
+            "Type": "Notification",
+            "MessageId": "95df01b4-ee98-5cb9-9903-4c221d41eb5e",
+            "TopicArn": "arn:aws:sns:us-east-2:123456789012:prices",
+            "Message": r#"{"sku":"SKU-2","price":250}"#,
+            "Timestamp": "2020-01-02T12:45:07.000Z",
+            "MessageAttributes": {"region": {"Type": "String", "Value": "eu"}}
+        });
+        let mut sns_over_sqs = sns_over_sqs();
+        let response = sns_over_sqs.run(sqs_event(&envelope.to_string()), Context::default()).unwrap();
+        assert_eq!(BatchResponse::default(), response);
+        assert_eq!(vec![("SKU-2".to_string(), 250, "".to_string(), "eu".to_string(), "arn:aws:sns:us-east-2:123456789012:prices".to_string())],
+                   sns_over_sqs.notification.application.received);
+    }
+
+    #[test]
+    fn test_sqs_raw_message_delivery() {
+        let mut sns_over_sqs = sns_over_sqs();
+        let response = sns_over_sqs.run(sqs_event(r#"{"sku":"SKU-3","price":100}"#), Context::default()).unwrap();
+        assert_eq!(BatchResponse::default(), response);
+        assert_eq!(vec![("SKU-3".to_string(), 100, "".to_string(), "us".to_string(), "".to_string())], sns_over_sqs.notification.application.received);
+
+        let response = sns_over_sqs.run(sqs_event(r#"{"sku":"SKU-3","price":0}"#), Context::default()).unwrap();
+        assert_eq!(vec![BatchItemFailure { item_identifier: "msg-1".to_string() }], response.batch_item_failures);
+    }
+}
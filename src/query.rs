@@ -0,0 +1,258 @@
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::components::SrvrlsError;
+
+/// Deserializes a multi-value query parameter map into `T`. Repeated keys may be deserialized into
+/// a sequence, for any other type the last value provided is used.
+pub(crate) fn from_query_parameters<T: DeserializeOwned>(parameters: &HashMap<String, Vec<String>>) -> Result<T, SrvrlsError> {
+    T::deserialize(QueryDeserializer { parameters })
+        .map_err(|e| SrvrlsError::BadRequestWithSimpleMessage(e.0))
+}
+
+#[derive(Debug)]
+struct QueryError(String);
+
+impl de::Error for QueryError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        QueryError(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        QueryError(format!("missing query parameter `{}`", field))
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+struct QueryDeserializer<'a> {
+    parameters: &'a HashMap<String, Vec<String>>,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for QueryDeserializer<'a> {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(QueryMapAccess { parameters: self.parameters.iter(), current: None })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct QueryMapAccess<'a> {
+    parameters: hash_map::Iter<'a, String, Vec<String>>,
+    current: Option<(&'a String, &'a Vec<String>)>,
+}
+
+impl<'de, 'a> MapAccess<'de> for QueryMapAccess<'a> {
+    type Error = QueryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error> {
+        match self.parameters.next() {
+            None => Ok(None),
+            Some((key, values)) => {
+                self.current = Some((key, values));
+                seed.deserialize(key.as_str().into_deserializer()).map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Self::Error> {
+        let (key, values) = self.current.take().ok_or_else(|| QueryError("value requested before key".to_string()))?;
+        seed.deserialize(ValueDeserializer { values })
+            .map_err(|e| QueryError(format!("invalid query parameter `{}`: {}", key, e)))
+    }
+}
+
+struct ValueDeserializer<'a> {
+    values: &'a [String],
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn value(&self) -> &'a str {
+        self.values.last().map_or("", |value| value.as_str())
+    }
+
+    fn parse<T: FromStr>(&self) -> Result<T, QueryError>
+        where T::Err: fmt::Display
+    {
+        self.value().parse().map_err(|e| QueryError(format!("`{}` {}", self.value(), e)))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.values.len() {
+            1 => visitor.visit_str(self.value()),
+            _ => self.deserialize_seq(visitor),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.value())
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_str(self.value())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.values.is_empty() {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(ValueSeqAccess { values: self.values.iter() })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.value().into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 bytes byte_buf unit unit_struct tuple_struct map struct identifier
+    }
+}
+
+struct ValueSeqAccess<'a> {
+    values: std::slice::Iter<'a, String>,
+}
+
+impl<'de, 'a> SeqAccess<'de> for ValueSeqAccess<'a> {
+    type Error = QueryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error> {
+        match self.values.next() {
+            None => Ok(None),
+            Some(value) => seed.deserialize(ValueDeserializer { values: std::slice::from_ref(value) }).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        Active,
+        Closed,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct CustomerSearch {
+        name: String,
+        limit: u32,
+        active: Option<bool>,
+        #[serde(default)]
+        status: Vec<Status>,
+        #[serde(default = "default_page")]
+        page: u64,
+        min_balance: Option<f64>,
+    }
+
+    fn default_page() -> u64 { 1 }
+
+    fn query_parameters(pairs: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+        let mut parameters: HashMap<String, Vec<String>> = HashMap::new();
+        for (key, value) in pairs {
+            parameters.entry(key.to_string()).or_default().push(value.to_string());
+        }
+        parameters
+    }
+
+    #[test]
+    fn test_from_query_parameters() {
+        let parameters = query_parameters(&[("name", "Steve"), ("limit", "25"), ("status", "active"), ("status", "closed"), ("unused", "x")]);
+        let search: CustomerSearch = from_query_parameters(&parameters).unwrap();
+        assert_eq!(CustomerSearch {
+            name: "Steve".to_string(),
+            limit: 25,
+            active: None,
+            status: vec![Status::Active, Status::Closed],
+            page: 1,
+            min_balance: None,
+        }, search);
+
+        let parameters = query_parameters(&[("name", "Steve"), ("limit", "25"), ("active", "true"), ("page", "3"), ("min_balance", "10.5")]);
+        let search: CustomerSearch = from_query_parameters(&parameters).unwrap();
+        assert_eq!(Some(true), search.active);
+        assert_eq!(3, search.page);
+        assert_eq!(Some(10.5), search.min_balance);
+    }
+
+    #[test]
+    fn test_from_query_parameters_errors() {
+        let result: Result<CustomerSearch, SrvrlsError> = from_query_parameters(&query_parameters(&[("name", "Steve"), ("limit", "ten")]));
+        assert_eq!(SrvrlsError::BadRequestWithSimpleMessage("invalid query parameter `limit`: `ten` invalid digit found in string".to_string()),
+                   result.unwrap_err());
+
+        let result: Result<CustomerSearch, SrvrlsError> = from_query_parameters(&query_parameters(&[("name", "Steve")]));
+        assert_eq!(SrvrlsError::BadRequestWithSimpleMessage("missing query parameter `limit`".to_string()), result.unwrap_err());
+
+        let result: Result<CustomerSearch, SrvrlsError> = from_query_parameters(&query_parameters(&[("name", "Steve"), ("limit", "1"), ("status", "pending")]));
+        match result.unwrap_err() {
+            SrvrlsError::BadRequestWithSimpleMessage(message) => assert!(message.starts_with("invalid query parameter `status`: unknown variant `pending`")),
+            e => panic!("unexpected error {}", e),
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet};
+
+use aws_lambda_events::event::sqs::{SqsEvent, SqsMessage, SqsMessageAttribute};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::{error, warn};
+use serde::de::DeserializeOwned;
+
+use crate::components::SrvrlsEventError;
+use crate::events::{BatchItemFailure, BatchResponse};
+
+/// A single SQS message with its' body deserialized from JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueMessage<M> {
+    /// The SQS message id.
+    pub message_id: String,
+    /// The deserialized message body.
+    pub body: M,
+    /// The SQS system attributes (e.g., `ApproximateReceiveCount`).
+    pub attributes: HashMap<String, String>,
+    /// The message attributes set by the sender.
+    pub message_attributes: HashMap<String, SqsMessageAttribute>,
+    /// The ARN of the queue the message was received from.
+    pub event_source_arn: String,
+}
+
+impl<M> QueueMessage<M> {
+    /// Provides the string value of a message attribute, or an empty string if it is missing.
+    #[must_use]
+    pub fn message_attribute(&self, name: &str) -> String {
+        match self.message_attributes.get(name).and_then(|attribute| attribute.string_value.as_ref()) {
+            None => "".to_string(),
+            Some(value) => value.clone(),
+        }
+    }
+}
+
+/// This trait should be implemented by your application to handle SQS messages, each message in a
+/// batch is passed to `handle` in turn.
+/// ```rust
+/// # use serde::Deserialize;
+/// # use srvrls::components::SrvrlsEventError;
+/// # use srvrls::queue::{QueueMessage, SrvrlsQueueApplication};
+/// #[derive(Deserialize)]
+/// struct OrderPlaced {
+///     order_id: String,
+/// }
+///
+/// struct OrderApplication {}
+///
+/// impl SrvrlsQueueApplication for OrderApplication {
+///     type Message = OrderPlaced;
+///
+///     fn handle(&mut self, message: QueueMessage<OrderPlaced>) -> Result<(), SrvrlsEventError> {
+///         match message.body.order_id.is_empty() {
+///             true => Err(SrvrlsEventError::NonRetryable("missing order id".to_string())),
+///             false => Ok(()),
+///         }
+///     }
+/// }
+/// ```
+pub trait SrvrlsQueueApplication {
+    /// The type each message body is deserialized into.
+    type Message: DeserializeOwned;
+
+    /// This method receives a single message of the batch.
+    ///
+    /// # Errors
+    /// A `SrvrlsEventError::Retryable` error returns the message to the queue to be delivered
+    /// again, a `SrvrlsEventError::NonRetryable` error is logged and the message is removed.
+    fn handle(&mut self, message: QueueMessage<Self::Message>) -> Result<(), SrvrlsEventError>;
+}
+
+/// `SrvrlsQueue` wraps your application that implements `SrvrlsQueueApplication` and interfaces
+/// with AWS Lambda, reporting only the messages that failed with a retryable error so that the
+/// rest of the batch is not delivered again.
+///
+/// For FIFO queues a retryable failure stops the rest of its' message group, the remaining messages
+/// of the group in the batch are not handled and are reported as failures so that their order is
+/// kept when they are delivered again.
+///
+/// Messages with a body that cannot be deserialized are treated as non-retryable. The event source
+/// mapping must have `ReportBatchItemFailures` enabled, otherwise the full batch is removed from
+/// the queue.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let queue = SrvrlsQueue::new(OrderApplication {});
+///     lambda!(queue);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsQueue<T: SrvrlsQueueApplication> {
+    application: T,
+}
+
+impl<T: SrvrlsQueueApplication> SrvrlsQueue<T> {
+    /// Create a new `SrvrlsQueue` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsQueue { application }
+    }
+
+    fn handle_message(&mut self, message: SqsMessage) -> Result<(), SrvrlsEventError> {
+        let body: T::Message = serde_json::from_str(message.body.as_deref().unwrap_or(""))
+            .map_err(|e| SrvrlsEventError::NonRetryable(format!("unable to deserialize message body: {}", e)))?;
+        self.application.handle(QueueMessage {
+            message_id: message.message_id.unwrap_or_default(),
+            body,
+            attributes: message.attributes,
+            message_attributes: message.message_attributes,
+            event_source_arn: message.event_source_arn.unwrap_or_default(),
+        })
+    }
+}
+
+impl<T: SrvrlsQueueApplication> Handler<SqsEvent, BatchResponse, HandlerError> for SrvrlsQueue<T> {
+    fn run(&mut self, event: SqsEvent, _ctx: Context) -> Result<BatchResponse, HandlerError> {
+        Ok(process_batch(event.records, |message| self.handle_message(message)))
+    }
+}
+
+/// Passes each message of an SQS batch to `handle`, reporting the messages that failed with a
+/// retryable error as batch item failures. Once a message of a FIFO message group has failed, the
+/// remaining messages of that group are reported as failures without being handled.
+pub(crate) fn process_batch<F>(records: Vec<SqsMessage>, mut handle: F) -> BatchResponse
+    where F: FnMut(SqsMessage) -> Result<(), SrvrlsEventError> {
+    let mut batch_item_failures = Vec::new();
+    let mut failed_groups = HashSet::new();
+    for message in records {
+        let message_id = message.message_id.clone().unwrap_or_default();
+        let message_group_id = message.attributes.get("MessageGroupId").cloned();
+        if message_group_id.as_ref().is_some_and(|group| failed_groups.contains(group)) {
+            warn!("message {} skipped after an earlier failure in its message group", message_id);
+            batch_item_failures.push(BatchItemFailure { item_identifier: message_id });
+            continue;
+        }
+        match handle(message) {
+            Ok(()) => {}
+            Err(SrvrlsEventError::Retryable(msg)) => {
+                warn!("message {} failed and will be retried: {}", message_id, msg);
+                batch_item_failures.push(BatchItemFailure { item_identifier: message_id });
+                failed_groups.extend(message_group_id);
+            }
+            Err(SrvrlsEventError::NonRetryable(msg)) => {
+                error!("message {} failed and will not be retried: {}", message_id, msg);
+            }
+        }
+    }
+    BatchResponse { batch_item_failures }
+}
+
+#[cfg(test)]
+mod queue_tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Payment {
+        amount: i64,
+    }
+
+    struct PaymentApplication {
+        processed: Vec<String>,
+    }
+
+    impl SrvrlsQueueApplication for PaymentApplication {
+        type Message = Payment;
+
+        fn handle(&mut self, message: QueueMessage<Payment>) -> Result<(), SrvrlsEventError> {
+            match message.body.amount {
+                amount if amount < 0 => Err(SrvrlsEventError::NonRetryable("negative amount".to_string())),
+                0 => Err(SrvrlsEventError::Retryable("ledger unavailable".to_string())),
+                _ => {
+                    self.processed.push(format!("{}:{}", message.message_id, message.message_attribute("tenant")));
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn message(message_id: &str, body: &str) -> serde_json::Value {
+        json!({
+            "messageId": message_id,
+            "receiptHandle": "AQEBwJnKyrHigUMZj6rYigCgxlaS3SLy0a",
+            "body": body,
+            "attributes": {"ApproximateReceiveCount": "1"},
+            "messageAttributes": {
+                "tenant": {"stringValue": "acme", "stringListValues": [], "binaryListValues": [], "dataType": "String"}
+            },
+            "md5OfBody": "e4e68fb7bd0e697a0ae8f1bb342846b3",
+            "eventSource": "aws:sqs",
+            "eventSourceARN": "arn:aws:sqs:us-east-2:123456789012:payments",
+            "awsRegion": "us-east-2"
+        })
+    }
+
+    #[test]
+    fn test_partial_batch_failure() {
+        let event: SqsEvent = serde_json::from_value(json!({"Records": [
+            message("msg-1", r#"{"amount": 100}"#),
+            message("msg-2", r#"{"amount": 0}"#),
+            message("msg-3", r#"{"amount": -5}"#),
+            message("msg-4", "not json"),
+            message("msg-5", r#"{"amount": 7}"#),
+        ]})).unwrap();
+        let mut queue = SrvrlsQueue::new(PaymentApplication { processed: Vec::new() });
+        let response = queue.run(event, Context::default()).unwrap();
+
+        assert_eq!(BatchResponse {
+            batch_item_failures: vec![BatchItemFailure { item_identifier: "msg-2".to_string() }],
+        }, response);
+        assert_eq!(vec!["msg-1:acme".to_string(), "msg-5:acme".to_string()], queue.application.processed);
+        assert_eq!(r#"{"batchItemFailures":[{"itemIdentifier":"msg-2"}]}"#, serde_json::to_string(&response).unwrap());
+    }
+
+    fn fifo_message(message_id: &str, body: &str, message_group_id: &str) -> serde_json::Value {
+        let mut message = message(message_id, body);
+        message["attributes"]["MessageGroupId"] = json!(message_group_id);
+        message["attributes"]["SequenceNumber"] = json!("18849496460467696128");
+        message["eventSourceARN"] = json!("arn:aws:sqs:us-east-2:123456789012:payments.fifo");
+        message
+    }
+
+    #[test]
+    fn test_fifo_group_failure() {
+        let event: SqsEvent = serde_json::from_value(json!({"Records": [
+            fifo_message("msg-1", r#"{"amount": 100}"#, "account-1"),
+            fifo_message("msg-2", r#"{"amount": 0}"#, "account-1"),
+            fifo_message("msg-3", r#"{"amount": 5}"#, "account-2"),
+            fifo_message("msg-4", r#"{"amount": 7}"#, "account-1"),
+            fifo_message("msg-5", r#"{"amount": -5}"#, "account-2"),
+            fifo_message("msg-6", r#"{"amount": 9}"#, "account-2"),
+        ]})).unwrap();
+        let mut queue = SrvrlsQueue::new(PaymentApplication { processed: Vec::new() });
+        let response = queue.run(event, Context::default()).unwrap();
+
+        assert_eq!(vec![
+            BatchItemFailure { item_identifier: "msg-2".to_string() },
+            BatchItemFailure { item_identifier: "msg-4".to_string() },
+        ], response.batch_item_failures);
+        assert_eq!(vec!["msg-1:acme".to_string(), "msg-3:acme".to_string(), "msg-6:acme".to_string()], queue.application.processed);
+    }
+}
@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use serde::Serialize;
 
+use crate::cookie::Cookie;
+
 /// The struct used to house details of the call response for custom responses.
 /// For most responses the helper methods are most useful.
 #[derive(Debug, Clone, PartialEq)]
@@ -10,8 +12,19 @@ pub struct SrvrlsResponse {
     pub status_code: i32,
     /// Any custom response headers, this will be improved by any configured `header_interceptor`.
     pub headers: HashMap<String, String>,
+    /// Any custom response headers with multiple values (e.g., `Vary` or `Link`), each value is
+    /// sent as a separate header. This will be improved by any configured
+    /// `multi_value_header_interceptor`.
+    pub multi_value_headers: HashMap<String, Vec<String>>,
     /// Response body.
     pub body: Option<String>,
+    /// Whether the body is a JSON serialized payload, which is rendered for the content type
+    /// requested in the `Accept` header. This is set by the helper methods that take a body.
+    pub is_payload: bool,
+    /// Whether the `body` is base64 encoded, as is the case for binary payloads.
+    pub is_base64_encoded: bool,
+    /// Cookies to be set on the client, each is sent as a separate `Set-Cookie` header.
+    pub cookies: Vec<Cookie>,
 }
 
 /// A simple error message wrapper.
@@ -21,6 +34,30 @@ pub struct SimpleError {
 }
 
 impl SrvrlsResponse {
+    /// Adds a value to a multi-value response header, keeping any values already present.
+    /// ```rust
+    /// # use srvrls::response::SrvrlsResponse;
+    /// let mut response = SrvrlsResponse::ok_empty();
+    /// response.add_header_value("Link", "</customer/2>; rel=\"next\"");
+    /// response.add_header_value("Link", "</customer/0>; rel=\"prev\"");
+    /// assert_eq!(2, response.multi_value_headers["Link"].len());
+    /// ```
+    pub fn add_header_value(&mut self, name: &str, value: &str) {
+        self.multi_value_headers.entry(name.to_string()).or_default().push(value.to_string());
+    }
+
+    /// Adds a cookie to be set on the client.
+    /// ```rust
+    /// # use srvrls::cookie::Cookie;
+    /// # use srvrls::response::SrvrlsResponse;
+    /// let mut response = SrvrlsResponse::no_content();
+    /// response.add_cookie(Cookie::new("session", "38afes7a8").secure().http_only());
+    /// response.add_cookie(Cookie::removal("cart"));
+    /// assert_eq!(2, response.cookies.len());
+    /// ```
+    pub fn add_cookie(&mut self, cookie: Cookie) {
+        self.cookies.push(cookie);
+    }
 
     /// Wraps a simple String error message in a [`SimpleError`] to be serialized for the response.
     #[must_use]
@@ -50,6 +87,11 @@ impl SrvrlsResponse {
     #[must_use]
     pub fn no_content() -> SrvrlsResponse { SrvrlsResponse::with_status(204) }
 
+    /// Helper method to provide a response for 303 - See Other, the `Location` header should be
+    /// added to this response
+    #[must_use]
+    pub fn see_other() -> SrvrlsResponse { SrvrlsResponse::with_status(303) }
+
     /// Helper method to provide a response for 400 - Bad Request
     #[must_use]
     pub fn bad_request<T: Serialize>(body: T) -> SrvrlsResponse { SrvrlsResponse::with_status_and_body(400, body) }
@@ -70,6 +112,14 @@ impl SrvrlsResponse {
     #[must_use]
     pub fn method_not_allowed() -> SrvrlsResponse { SrvrlsResponse::with_status(405) }
 
+    /// Helper method to provide a response for 406 - Not Acceptable
+    #[must_use]
+    pub fn not_acceptable() -> SrvrlsResponse { SrvrlsResponse::with_status(406) }
+
+    /// Helper method to provide a response for 413 - Payload Too Large
+    #[must_use]
+    pub fn payload_too_large() -> SrvrlsResponse { SrvrlsResponse::with_status(413) }
+
     /// Helper method to provide a response for 500 - Internal Server Error
     #[must_use]
     pub fn internal_server_error() -> SrvrlsResponse { SrvrlsResponse::with_status(500) }
@@ -82,20 +132,24 @@ impl SrvrlsResponse {
         SrvrlsResponse {
             status_code,
             headers: HashMap::default(),
+            multi_value_headers: HashMap::default(),
             body: None,
+            is_payload: false,
+            is_base64_encoded: false,
+            cookies: Vec::new(),
         }
     }
     fn with_status_and_body<T: Serialize>(status_code: i32, body: T) -> SrvrlsResponse {
         SrvrlsResponse {
             status_code,
             headers: HashMap::default(),
-            body: Some(SrvrlsResponse::derive_body(body)),
+            multi_value_headers: HashMap::default(),
+            body: Some(serde_json::to_string(&body).unwrap()),
+            is_payload: true,
+            is_base64_encoded: false,
+            cookies: Vec::new(),
         }
     }
-
-    fn derive_body<T: Serialize>(body: T) -> String {
-        serde_json::to_string(&body).unwrap()
-    }
 }
 
 #[cfg(test)]
@@ -119,6 +173,25 @@ mod response_tests {
         assert_eq!(res.body, Some(r#"{"id":"tst-2BAC456","name":"Jimmy Jones"}"#.to_string()));
     }
 
+    #[derive(Serialize)]
+    struct OrderDto {
+        order_id: String,
+        customer: String,
+        amount: i64,
+    }
+
+    #[test]
+    fn test_field_order() {
+        let res = SrvrlsResponse::ok(OrderDto {
+            order_id: "ORD-1".to_string(),
+            customer: "Jimmy Jones".to_string(),
+            amount: 250,
+        });
+
+        assert_eq!(res.body, Some(r#"{"order_id":"ORD-1","customer":"Jimmy Jones","amount":250}"#.to_string()));
+        assert!(res.is_payload);
+    }
+
     #[test]
     fn test_ok_empty() {
         let res = SrvrlsResponse::ok_empty();
@@ -0,0 +1,279 @@
+use serde_json::Value;
+
+use crate::components::{split_header_value, SrvrlsError};
+
+/// A serializer used to render the payload of a `SrvrlsResponse`, the serializer is selected by
+/// matching its' content type against the `Accept` header of the request.
+/// ```rust
+/// # use serde_json::Value;
+/// # use srvrls::components::SrvrlsError;
+/// # use srvrls::serializer::SrvrlsSerializer;
+/// struct PlainTextSerializer {}
+///
+/// impl SrvrlsSerializer for PlainTextSerializer {
+///     fn content_type(&self) -> &str { "text/plain" }
+///
+///     fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+///         Ok(payload.to_string().into_bytes())
+///     }
+/// }
+/// ```
+pub trait SrvrlsSerializer {
+    /// The media type produced by this serializer, this is also used for the `Content-Type` header.
+    fn content_type(&self) -> &str;
+
+    /// Whether the serialized payload is binary and must be base64 encoded in the response.
+    fn is_binary(&self) -> bool {
+        false
+    }
+
+    /// Serializes the response payload.
+    ///
+    /// # Errors
+    /// Errors returned by this method will be mapped to a (4xx or 5xx) response.
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError>;
+
+    /// Serializes a response payload that has already been serialized as JSON, by default the JSON
+    /// is parsed and passed to `serialize`.
+    ///
+    /// # Errors
+    /// Errors returned by this method will be mapped to a (4xx or 5xx) response.
+    fn serialize_json(&self, json: &str) -> Result<Vec<u8>, SrvrlsError> {
+        let payload = serde_json::from_str(json).map_err(|_| SrvrlsError::InternalServerError)?;
+        self.serialize(&payload)
+    }
+}
+
+/// Serializes the payload as compact JSON, this is the default serializer. The body is sent as
+/// serialized by the response, keeping the field order of the payload.
+pub struct JsonSerializer {}
+
+impl SrvrlsSerializer for JsonSerializer {
+    fn content_type(&self) -> &str { "application/json" }
+
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+        serde_json::to_vec(payload).map_err(|_| SrvrlsError::InternalServerError)
+    }
+
+    fn serialize_json(&self, json: &str) -> Result<Vec<u8>, SrvrlsError> {
+        Ok(json.as_bytes().to_vec())
+    }
+}
+
+/// Serializes the payload as indented JSON with its' fields sorted by name, this replaces the default
+/// `JsonSerializer` when added.
+pub struct PrettyJsonSerializer {}
+
+impl SrvrlsSerializer for PrettyJsonSerializer {
+    fn content_type(&self) -> &str { "application/json" }
+
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+        serde_json::to_vec_pretty(payload).map_err(|_| SrvrlsError::InternalServerError)
+    }
+}
+
+/// Serializes the payload as CBOR, this requires the `cbor` feature.
+#[cfg(feature = "cbor")]
+pub struct CborSerializer {}
+
+#[cfg(feature = "cbor")]
+impl SrvrlsSerializer for CborSerializer {
+    fn content_type(&self) -> &str { "application/cbor" }
+
+    fn is_binary(&self) -> bool { true }
+
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+        serde_cbor::to_vec(payload).map_err(|_| SrvrlsError::InternalServerError)
+    }
+}
+
+/// Serializes the payload as MessagePack, with structs encoded as maps. This requires the
+/// `msgpack` feature.
+#[cfg(feature = "msgpack")]
+pub struct MessagePackSerializer {}
+
+#[cfg(feature = "msgpack")]
+impl SrvrlsSerializer for MessagePackSerializer {
+    fn content_type(&self) -> &str { "application/msgpack" }
+
+    fn is_binary(&self) -> bool { true }
+
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+        rmp_serde::to_vec_named(payload).map_err(|_| SrvrlsError::InternalServerError)
+    }
+}
+
+/// Serializes the payload as YAML, this requires the `yaml` feature.
+#[cfg(feature = "yaml")]
+pub struct YamlSerializer {}
+
+#[cfg(feature = "yaml")]
+impl SrvrlsSerializer for YamlSerializer {
+    fn content_type(&self) -> &str { "application/yaml" }
+
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+        serde_yaml::to_vec(payload).map_err(|_| SrvrlsError::InternalServerError)
+    }
+}
+
+/// Serializes the payload as CSV, this requires the `csv` feature. The payload must be an object or
+/// an array of objects. The header row is taken from the fields of the first object, sorted by
+/// name, nested values are written as JSON.
+#[cfg(feature = "csv")]
+pub struct CsvSerializer {}
+
+#[cfg(feature = "csv")]
+impl SrvrlsSerializer for CsvSerializer {
+    fn content_type(&self) -> &str { "text/csv" }
+
+    fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+        let rows = match payload {
+            Value::Array(rows) => rows.iter().collect(),
+            Value::Object(_) => vec![payload],
+            _ => return Err(SrvrlsError::InternalServerError),
+        };
+        let columns: Vec<&String> = match rows.first() {
+            Some(Value::Object(first)) => first.keys().collect(),
+            Some(_) => return Err(SrvrlsError::InternalServerError),
+            None => Vec::new(),
+        };
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&columns).map_err(|_| SrvrlsError::InternalServerError)?;
+        for row in rows {
+            let row = row.as_object().ok_or(SrvrlsError::InternalServerError)?;
+            let record = columns.iter().map(|column| match row.get(*column) {
+                None | Some(Value::Null) => "".to_string(),
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            });
+            writer.write_record(record).map_err(|_| SrvrlsError::InternalServerError)?;
+        }
+        writer.into_inner().map_err(|_| SrvrlsError::InternalServerError)
+    }
+}
+
+/// Selects the serializer with the highest quality in the `Accept` header, the first serializer is
+/// used when no `Accept` header was sent. Ties are resolved by the order the serializers were added.
+pub(crate) fn negotiate<'a>(accept: &str, serializers: &'a [Box<dyn SrvrlsSerializer>]) -> Option<&'a dyn SrvrlsSerializer> {
+    if accept.trim().is_empty() {
+        return serializers.first().map(AsRef::as_ref);
+    }
+    let ranges: Vec<(String, f32)> = accept.split(',')
+        .map(|range| {
+            let (media_range, parameters) = split_header_value(range);
+            let quality = parameters.get("q").and_then(|q| q.parse().ok()).unwrap_or(1.0);
+            (media_range.to_ascii_lowercase(), quality)
+        })
+        .collect();
+    let mut selected = None;
+    let mut selected_quality = 0.0;
+    for serializer in serializers {
+        let quality = quality(&ranges, serializer.content_type());
+        if quality > selected_quality {
+            selected = Some(serializer.as_ref());
+            selected_quality = quality;
+        }
+    }
+    selected
+}
+
+/// The quality of the most specific media range matching the content type, or zero if none match.
+fn quality(ranges: &[(String, f32)], content_type: &str) -> f32 {
+    let content_type = content_type.to_ascii_lowercase();
+    let main_type = content_type.split('/').next().unwrap_or("");
+    ranges.iter()
+        .filter_map(|(media_range, quality)| {
+            let specificity = if *media_range == content_type {
+                2
+            } else if media_range.strip_suffix("/*") == Some(main_type) {
+                1
+            } else if media_range == "*/*" {
+                0
+            } else {
+                return None;
+            };
+            Some((specificity, *quality))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or(0.0, |(_, quality)| quality)
+}
+
+#[cfg(test)]
+mod serializer_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    struct PlainTextSerializer {}
+
+    impl SrvrlsSerializer for PlainTextSerializer {
+        fn content_type(&self) -> &str { "text/plain" }
+
+        fn serialize(&self, payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+            Ok(payload.to_string().into_bytes())
+        }
+    }
+
+    struct XmlSerializer {}
+
+    impl SrvrlsSerializer for XmlSerializer {
+        fn content_type(&self) -> &str { "application/xml" }
+
+        fn serialize(&self, _payload: &Value) -> Result<Vec<u8>, SrvrlsError> {
+            Ok(b"<payload/>".to_vec())
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let serializers: Vec<Box<dyn SrvrlsSerializer>> = vec![Box::new(JsonSerializer {}), Box::new(PlainTextSerializer {}), Box::new(XmlSerializer {})];
+        let negotiated = |accept: &str| negotiate(accept, &serializers).map(|serializer| serializer.content_type().to_string());
+
+        assert_eq!(Some("application/json".to_string()), negotiated(""));
+        assert_eq!(Some("application/json".to_string()), negotiated("*/*"));
+        assert_eq!(Some("text/plain".to_string()), negotiated("text/plain"));
+        assert_eq!(Some("text/plain".to_string()), negotiated("text/*, application/json;q=0.5"));
+        assert_eq!(Some("application/xml".to_string()), negotiated("application/json;q=0.2, application/xml;q=0.8"));
+        assert_eq!(Some("application/xml".to_string()), negotiated("*/*;q=0.1, application/json;q=0, text/plain;q=0, application/XML"));
+        assert_eq!(Some("text/plain".to_string()), negotiated("text/*;q=0.5, application/*;q=0.1"));
+        assert_eq!(None, negotiated("text/html, image/png"));
+        assert_eq!(None, negotiated("*/*;q=0"));
+    }
+
+    #[test]
+    fn test_json_serializers() {
+        let pretty = String::from_utf8(PrettyJsonSerializer {}.serialize(&json!({"id": "tst-1"})).unwrap()).unwrap();
+        assert_eq!("{\n  \"id\": \"tst-1\"\n}", pretty);
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn test_csv_serializer() {
+        let payload = json!([{"id": "tst-1", "count": 2, "tags": ["a"]}, {"id": "tst-2", "count": null}]);
+        assert_eq!(b"count,id,tags\n2,tst-1,\"[\"\"a\"\"]\"\n,tst-2,\n".to_vec(), CsvSerializer {}.serialize(&payload).unwrap());
+        assert_eq!(SrvrlsError::InternalServerError, CsvSerializer {}.serialize(&json!("text")).unwrap_err());
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn test_yaml_serializer() {
+        let yaml = String::from_utf8(YamlSerializer {}.serialize(&json!({"id": "tst-1"})).unwrap()).unwrap();
+        assert_eq!("---\nid: tst-1\n", yaml);
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn test_cbor_serializer() {
+        let payload = json!([{"id": "tst-1", "count": 2, "tags": ["a"]}]);
+        let cbor = CborSerializer {}.serialize(&payload).unwrap();
+        assert_eq!(payload, serde_cbor::from_slice::<Value>(&cbor).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "msgpack")]
+    fn test_message_pack_serializer() {
+        let payload = json!([{"id": "tst-1", "count": 2, "tags": ["a"]}]);
+        let message_pack = MessagePackSerializer {}.serialize(&payload).unwrap();
+        assert_eq!(payload, rmp_serde::from_slice::<Value>(&message_pack).unwrap());
+    }
+}
@@ -0,0 +1,191 @@
+use aws_lambda_events::event::s3::{S3Event, S3EventRecord};
+use chrono::{DateTime, Utc};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::error;
+
+use crate::components::{percent_decode, SrvrlsEventError};
+
+/// A single S3 object notification, with the object key url decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectNotification {
+    /// The event name, e.g. `ObjectCreated:Put` or `ObjectRemoved:Delete`.
+    pub event_name: String,
+    /// The time of the event.
+    pub event_time: DateTime<Utc>,
+    /// The bucket name.
+    pub bucket: String,
+    /// The object key, decoded from the url encoded key in the event.
+    pub key: String,
+    /// The size of the object in bytes.
+    pub size: i64,
+    /// The object ETag, or an empty string if none was provided.
+    pub etag: String,
+    /// The object version, if the bucket is versioned.
+    pub version_id: Option<String>,
+    /// The sequencer, used to order events for the same key.
+    pub sequencer: String,
+}
+
+impl From<S3EventRecord> for ObjectNotification {
+    fn from(record: S3EventRecord) -> Self {
+        let object = record.s3.object;
+        ObjectNotification {
+            event_name: record.event_name.unwrap_or_default(),
+            event_time: record.event_time,
+            bucket: record.s3.bucket.name.unwrap_or_default(),
+            key: percent_decode(&object.key.unwrap_or_default(), true),
+            size: object.size,
+            etag: object.e_tag.unwrap_or_default(),
+            version_id: object.version_id.filter(|version_id| !version_id.is_empty()),
+            sequencer: object.sequencer.unwrap_or_default(),
+        }
+    }
+}
+
+/// This trait should be implemented by your application to handle S3 object notifications, each
+/// record of the event is passed to `handle` in turn.
+/// ```rust
+/// # use srvrls::components::SrvrlsEventError;
+/// # use srvrls::storage::{ObjectNotification, SrvrlsStorageApplication};
+/// struct IngestApplication {}
+///
+/// impl SrvrlsStorageApplication for IngestApplication {
+///     fn handle(&mut self, notification: ObjectNotification) -> Result<(), SrvrlsEventError> {
+///         match notification.key.ends_with(".csv") {
+///             true => Ok(()),
+///             false => Err(SrvrlsEventError::NonRetryable("only csv files are ingested".to_string())),
+///         }
+///     }
+/// }
+/// ```
+pub trait SrvrlsStorageApplication {
+    /// This method receives a single object notification.
+    ///
+    /// # Errors
+    /// A `SrvrlsEventError::Retryable` error causes the event to be delivered again, a
+    /// `SrvrlsEventError::NonRetryable` error is logged along with the object key.
+    fn handle(&mut self, notification: ObjectNotification) -> Result<(), SrvrlsEventError>;
+}
+
+/// `SrvrlsStorage` wraps your application that implements `SrvrlsStorageApplication` and
+/// interfaces with AWS Lambda for S3 events.
+///
+/// Every record is processed even if an earlier record fails. If any record fails with a
+/// retryable error the invocation fails with an error listing each failed key, so that AWS Lambda
+/// retries the (asynchronous) delivery. Note that the full event is retried, so handling should be
+/// idempotent.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let storage = SrvrlsStorage::new(IngestApplication {});
+///     lambda!(storage);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsStorage<T: SrvrlsStorageApplication> {
+    application: T,
+}
+
+impl<T: SrvrlsStorageApplication> SrvrlsStorage<T> {
+    /// Create a new `SrvrlsStorage` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsStorage { application }
+    }
+}
+
+impl<T: SrvrlsStorageApplication> Handler<S3Event, (), HandlerError> for SrvrlsStorage<T> {
+    fn run(&mut self, event: S3Event, _ctx: Context) -> Result<(), HandlerError> {
+        let total = event.records.len();
+        let mut failures = Vec::new();
+        for record in event.records {
+            let notification = ObjectNotification::from(record);
+            let key = format!("{}/{}", notification.bucket, notification.key);
+            match self.application.handle(notification) {
+                Ok(()) => {}
+                Err(SrvrlsEventError::Retryable(msg)) => failures.push(format!("{}: {}", key, msg)),
+                Err(SrvrlsEventError::NonRetryable(msg)) => {
+                    error!("object {} failed and will not be retried: {}", key, msg);
+                }
+            }
+        }
+        match failures.is_empty() {
+            true => Ok(()),
+            false => {
+                let msg = format!("{} of {} objects failed and will be retried: {}", failures.len(), total, failures.join("; "));
+                error!("{}", msg);
+                Err(HandlerError::from(msg.as_str()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use serde_json::json;
+
+    use super::*;
+
+    struct IngestApplication {
+        ingested: Vec<String>,
+    }
+
+    impl SrvrlsStorageApplication for IngestApplication {
+        fn handle(&mut self, notification: ObjectNotification) -> Result<(), SrvrlsEventError> {
+            match notification.size {
+                0 => Err(SrvrlsEventError::Retryable("object not yet readable".to_string())),
+                size if size < 0 => Err(SrvrlsEventError::NonRetryable("invalid size".to_string())),
+                _ => {
+                    self.ingested.push(notification.key);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn record(key: &str, size: i64) -> serde_json::Value {
+        json!({
+            "eventVersion": "2.1",
+            "eventSource": "aws:s3",
+            "awsRegion": "us-east-2",
+            "eventTime": "2020-03-03T12:17:51.318Z",
+            "eventName": "ObjectCreated:Put",
+            "userIdentity": {"principalId": "AWS:AIDAINPONIXQXHT3IKHL2"},
+            "requestParameters": {"sourceIPAddress": "205.255.255.255"},
+            "responseElements": {},
+            "s3": {
+                "s3SchemaVersion": "1.0",
+                "configurationId": "ingest",
+                "bucket": {"name": "ingest-bucket", "ownerIdentity": {"principalId": "A3I5XTEXAMAI3E"}, "arn": "arn:aws:s3:::ingest-bucket"},
+                "object": {"key": key, "size": size, "eTag": "0123456789abcdef", "versionId": "", "sequencer": "0A1B2C3D4E5F678901"}
+            }
+        })
+    }
+
+    #[test]
+    fn test_object_notification() {
+        let event: S3Event = serde_json::from_value(json!({"Records": [record("reports/Q1+2020%2Fsummary%28final%29.csv", 1024)]})).unwrap();
+        let notification = ObjectNotification::from(event.records[0].clone());
+        assert_eq!("reports/Q1 2020/summary(final).csv", notification.key);
+        assert_eq!("ingest-bucket", notification.bucket);
+        assert_eq!("ObjectCreated:Put", notification.event_name);
+        assert_eq!("0123456789abcdef", notification.etag);
+        assert_eq!(None, notification.version_id);
+        assert_eq!(1024, notification.size);
+    }
+
+    #[test]
+    fn test_failure_aggregation() {
+        let event: S3Event = serde_json::from_value(json!({"Records": [
+            record("a.csv", 10),
+            record("b+c.csv", 0),
+            record("d.csv", -1),
+            record("e.csv", 5),
+        ]})).unwrap();
+        let mut storage = SrvrlsStorage::new(IngestApplication { ingested: Vec::new() });
+        let error = storage.run(event, Context::default()).unwrap_err();
+
+        assert_eq!(vec!["a.csv".to_string(), "e.csv".to_string()], storage.application.ingested);
+        assert!(format!("{:?}", error).contains("1 of 4 objects failed and will be retried: ingest-bucket/b c.csv: object not yet readable"));
+    }
+}
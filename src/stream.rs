@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::kinesis::{KinesisEvent, KinesisEventRecord};
+use chrono::{DateTime, TimeZone, Utc};
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::{error, warn};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+use crate::components::SrvrlsEventError;
+use crate::events::{BatchItemFailure, BatchResponse, DynamoDbEvent, DynamoDbEventRecord};
+
+/// A single Kinesis record with its' base64 encoded data decoded and deserialized from JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KinesisStreamRecord<M> {
+    /// The event id, `<shard id>:<sequence number>`.
+    pub event_id: String,
+    /// The id of the shard the record was read from.
+    pub shard_id: String,
+    /// The sequence number of the record within its' shard.
+    pub sequence_number: String,
+    /// The partition key the record was written with.
+    pub partition_key: String,
+    /// The ARN of the stream.
+    pub event_source_arn: String,
+    /// The approximate time the record was written to the stream.
+    pub approximate_arrival_timestamp: DateTime<Utc>,
+    /// The deserialized record data.
+    pub data: M,
+}
+
+/// A single DynamoDB Streams record with its' images converted from DynamoDB attribute values and
+/// deserialized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamoDbChange<I> {
+    /// The event id.
+    pub event_id: String,
+    /// The type of change, one of `INSERT`, `MODIFY` or `REMOVE`.
+    pub event_name: String,
+    /// The sequence number of the record within its' shard.
+    pub sequence_number: String,
+    /// The ARN of the stream.
+    pub event_source_arn: String,
+    /// The approximate time of the change.
+    pub approximate_creation_time: Option<DateTime<Utc>>,
+    /// The primary key attributes of the item as plain JSON, e.g. `{"id": "CUST-A23948"}`.
+    pub keys: Value,
+    /// The item after the change, absent for `REMOVE` or if the stream does not include new images.
+    pub new_image: Option<I>,
+    /// The item before the change, absent for `INSERT` or if the stream does not include old images.
+    pub old_image: Option<I>,
+}
+
+/// This trait should be implemented by your application to handle Kinesis records, the records of
+/// each shard are passed to `handle` in order.
+/// ```rust
+/// # use serde::Deserialize;
+/// # use srvrls::components::SrvrlsEventError;
+/// # use srvrls::stream::{KinesisStreamRecord, SrvrlsKinesisApplication};
+/// #[derive(Deserialize)]
+/// struct ClickEvent {
+///     page: String,
+/// }
+///
+/// struct ClickApplication {}
+///
+/// impl SrvrlsKinesisApplication for ClickApplication {
+///     type Record = ClickEvent;
+///
+///     fn handle(&mut self, record: KinesisStreamRecord<ClickEvent>) -> Result<(), SrvrlsEventError> {
+///         println!("{} clicked {}", record.partition_key, record.data.page);
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait SrvrlsKinesisApplication {
+    /// The type the data of each record is deserialized into.
+    type Record: DeserializeOwned;
+
+    /// This method receives a single record of the batch.
+    ///
+    /// # Errors
+    /// A `SrvrlsEventError::Retryable` error stops processing of the batch, this record and all
+    /// following records are delivered again. A `SrvrlsEventError::NonRetryable` error is logged
+    /// and processing continues with the next record.
+    fn handle(&mut self, record: KinesisStreamRecord<Self::Record>) -> Result<(), SrvrlsEventError>;
+}
+
+/// This trait should be implemented by your application to handle DynamoDB Streams records, the
+/// changes to each item are passed to `handle` in order.
+/// ```rust
+/// # use serde::Deserialize;
+/// # use srvrls::components::SrvrlsEventError;
+/// # use srvrls::stream::{DynamoDbChange, SrvrlsDynamoDbApplication};
+/// #[derive(Deserialize)]
+/// struct Customer {
+///     id: String,
+///     email: String,
+/// }
+///
+/// struct CustomerApplication {}
+///
+/// impl SrvrlsDynamoDbApplication for CustomerApplication {
+///     type Item = Customer;
+///
+///     fn handle(&mut self, change: DynamoDbChange<Customer>) -> Result<(), SrvrlsEventError> {
+///         if let Some(customer) = change.new_image {
+///             println!("customer {} now has email {}", customer.id, customer.email);
+///         }
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait SrvrlsDynamoDbApplication {
+    /// The type the new and old images are deserialized into.
+    type Item: DeserializeOwned;
+
+    /// This method receives a single change of the batch.
+    ///
+    /// # Errors
+    /// A `SrvrlsEventError::Retryable` error stops processing of the batch, this record and all
+    /// following records are delivered again. A `SrvrlsEventError::NonRetryable` error is logged
+    /// and processing continues with the next record.
+    fn handle(&mut self, change: DynamoDbChange<Self::Item>) -> Result<(), SrvrlsEventError>;
+}
+
+/// `SrvrlsKinesis` wraps your application that implements `SrvrlsKinesisApplication` and
+/// interfaces with AWS Lambda for Kinesis events.
+///
+/// Records are processed in order, the first record to fail with a retryable error is reported by
+/// its' sequence number so that the stream is checkpointed just before it. Records with data that
+/// cannot be deserialized are treated as non-retryable. The event source mapping must have
+/// `ReportBatchItemFailures` enabled, otherwise the full batch is considered processed.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let kinesis = SrvrlsKinesis::new(ClickApplication {});
+///     lambda!(kinesis);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsKinesis<T: SrvrlsKinesisApplication> {
+    application: T,
+}
+
+impl<T: SrvrlsKinesisApplication> SrvrlsKinesis<T> {
+    /// Create a new `SrvrlsKinesis` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsKinesis { application }
+    }
+
+    fn handle_record(&mut self, record: KinesisEventRecord) -> Result<(), SrvrlsEventError> {
+        let data: T::Record = serde_json::from_slice(&record.kinesis.data)
+            .map_err(|e| SrvrlsEventError::NonRetryable(format!("unable to deserialize record data: {}", e)))?;
+        let event_id = record.event_id.unwrap_or_default();
+        self.application.handle(KinesisStreamRecord {
+            shard_id: event_id.split(':').next().unwrap_or("").to_string(),
+            event_id,
+            sequence_number: record.kinesis.sequence_number.unwrap_or_default(),
+            partition_key: record.kinesis.partition_key.unwrap_or_default(),
+            event_source_arn: record.event_source_arn.unwrap_or_default(),
+            approximate_arrival_timestamp: *record.kinesis.approximate_arrival_timestamp,
+            data,
+        })
+    }
+}
+
+impl<T: SrvrlsKinesisApplication> Handler<KinesisEvent, BatchResponse, HandlerError> for SrvrlsKinesis<T> {
+    fn run(&mut self, event: KinesisEvent, _ctx: Context) -> Result<BatchResponse, HandlerError> {
+        Ok(process_in_order(event.records,
+                            |record| record.kinesis.sequence_number.clone().unwrap_or_default(),
+                            |record| self.handle_record(record)))
+    }
+}
+
+/// `SrvrlsDynamoDb` wraps your application that implements `SrvrlsDynamoDbApplication` and
+/// interfaces with AWS Lambda for DynamoDB Streams events.
+///
+/// Records are processed in order, the first record to fail with a retryable error is reported by
+/// its' sequence number so that the stream is checkpointed just before it. Records with images
+/// that cannot be deserialized are treated as non-retryable. The event source mapping must have
+/// `ReportBatchItemFailures` enabled, otherwise the full batch is considered processed.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let dynamodb = SrvrlsDynamoDb::new(CustomerApplication {});
+///     lambda!(dynamodb);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsDynamoDb<T: SrvrlsDynamoDbApplication> {
+    application: T,
+}
+
+impl<T: SrvrlsDynamoDbApplication> SrvrlsDynamoDb<T> {
+    /// Create a new `SrvrlsDynamoDb` instance to interface with AWS Lambda.
+    pub fn new(application: T) -> Self {
+        SrvrlsDynamoDb { application }
+    }
+
+    fn handle_record(&mut self, record: DynamoDbEventRecord) -> Result<(), SrvrlsEventError> {
+        let change = record.dynamodb;
+        let seconds = change.approximate_creation_date_time;
+        self.application.handle(DynamoDbChange {
+            event_id: record.event_id.unwrap_or_default(),
+            event_name: record.event_name.unwrap_or_default(),
+            sequence_number: change.sequence_number.unwrap_or_default(),
+            event_source_arn: record.event_source_arn.unwrap_or_default(),
+            approximate_creation_time: Utc.timestamp_millis_opt((seconds * 1000.0) as i64).single(),
+            keys: attribute_map(change.keys)?,
+            new_image: image(change.new_image)?,
+            old_image: image(change.old_image)?,
+        })
+    }
+}
+
+impl<T: SrvrlsDynamoDbApplication> Handler<DynamoDbEvent, BatchResponse, HandlerError> for SrvrlsDynamoDb<T> {
+    fn run(&mut self, event: DynamoDbEvent, _ctx: Context) -> Result<BatchResponse, HandlerError> {
+        Ok(process_in_order(event.records,
+                            |record| record.dynamodb.sequence_number.clone().unwrap_or_default(),
+                            |record| self.handle_record(record)))
+    }
+}
+
+fn process_in_order<R, S, F>(records: Vec<R>, sequence_number: S, mut handle: F) -> BatchResponse
+    where S: Fn(&R) -> String,
+          F: FnMut(R) -> Result<(), SrvrlsEventError>
+{
+    for record in records {
+        let sequence_number = sequence_number(&record);
+        match handle(record) {
+            Ok(()) => {}
+            Err(SrvrlsEventError::Retryable(msg)) => {
+                warn!("record {} failed and will be retried: {}", sequence_number, msg);
+                return BatchResponse {
+                    batch_item_failures: vec![BatchItemFailure { item_identifier: sequence_number }],
+                };
+            }
+            Err(SrvrlsEventError::NonRetryable(msg)) => {
+                error!("record {} failed and will not be retried: {}", sequence_number, msg);
+            }
+        }
+    }
+    BatchResponse::default()
+}
+
+fn image<I: DeserializeOwned>(image: Option<HashMap<String, Value>>) -> Result<Option<I>, SrvrlsEventError> {
+    match image {
+        None => Ok(None),
+        Some(image) => serde_json::from_value(attribute_map(image)?)
+            .map(Some)
+            .map_err(|e| SrvrlsEventError::NonRetryable(format!("unable to deserialize image: {}", e))),
+    }
+}
+
+fn attribute_map(attributes: HashMap<String, Value>) -> Result<Value, SrvrlsEventError> {
+    let mut map = Map::new();
+    for (name, value) in attributes {
+        map.insert(name, attribute_value(value)?);
+    }
+    Ok(Value::Object(map))
+}
+
+/// Converts a DynamoDB attribute value (e.g. `{"N": "42"}`) into plain JSON, binary values are
+/// left base64 encoded.
+fn attribute_value(value: Value) -> Result<Value, SrvrlsEventError> {
+    let invalid = || SrvrlsEventError::NonRetryable(format!("invalid attribute value: {}", value));
+    let (data_type, inner) = match value.as_object() {
+        Some(object) if object.len() == 1 => object.iter().next().ok_or_else(invalid)?,
+        _ => return Err(invalid()),
+    };
+    match (data_type.as_str(), inner) {
+        ("S", Value::String(_)) | ("B", Value::String(_)) | ("BOOL", Value::Bool(_)) => Ok(inner.clone()),
+        ("NULL", _) => Ok(Value::Null),
+        ("N", Value::String(number)) => number_value(number).ok_or_else(invalid),
+        ("SS", Value::Array(values)) | ("BS", Value::Array(values)) => Ok(Value::Array(values.clone())),
+        ("NS", Value::Array(values)) => values.iter()
+            .map(|number| number.as_str().and_then(number_value).ok_or_else(invalid))
+            .collect::<Result<Vec<Value>, SrvrlsEventError>>()
+            .map(Value::Array),
+        ("L", Value::Array(values)) => values.iter()
+            .map(|value| attribute_value(value.clone()))
+            .collect::<Result<Vec<Value>, SrvrlsEventError>>()
+            .map(Value::Array),
+        ("M", Value::Object(attributes)) => attribute_map(attributes.clone().into_iter().collect()),
+        _ => Err(invalid()),
+    }
+}
+
+fn number_value(number: &str) -> Option<Value> {
+    if let Ok(integer) = number.parse::<i64>() {
+        return Some(Value::from(integer));
+    }
+    if let Ok(integer) = number.parse::<u64>() {
+        return Some(Value::from(integer));
+    }
+    number.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number)
+}
+
+#[cfg(test)]
+mod stream_tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Reading {
+        sensor: String,
+        value: f64,
+    }
+
+    struct ReadingApplication {
+        processed: Vec<String>,
+    }
+
+    impl SrvrlsKinesisApplication for ReadingApplication {
+        type Record = Reading;
+
+        fn handle(&mut self, record: KinesisStreamRecord<Reading>) -> Result<(), SrvrlsEventError> {
+            match record.data.value {
+                value if value < 0.0 => Err(SrvrlsEventError::NonRetryable("negative reading".to_string())),
+                value if value > 100.0 => Err(SrvrlsEventError::Retryable("threshold service unavailable".to_string())),
+                _ => {
+                    self.processed.push(format!("{}:{}:{}", record.shard_id, record.sequence_number, record.data.sensor));
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn kinesis_record(sequence_number: &str, data: &str) -> Value {
+        json!({
+            "kinesis": {
+                "kinesisSchemaVersion": "1.0",
+                "partitionKey": "sensor-1",
+                "sequenceNumber": sequence_number,
+                "data": base64::encode(data),
+                "approximateArrivalTimestamp": 1545084650.987
+            },
+            "eventSource": "aws:kinesis",
+            "eventVersion": "1.0",
+            "eventID": format!("shardId-000000000006:{}", sequence_number),
+            "eventName": "aws:kinesis:record",
+            "invokeIdentityArn": "arn:aws:iam::123456789012:role/lambda-role",
+            "awsRegion": "us-east-2",
+            "eventSourceARN": "arn:aws:kinesis:us-east-2:123456789012:stream/readings"
+        })
+    }
+
+    #[test]
+    fn test_kinesis_first_failure() {
+        let event: KinesisEvent = serde_json::from_value(json!({"Records": [
+            kinesis_record("100", r#"{"sensor": "a", "value": 1.5}"#),
+            kinesis_record("101", r#"{"sensor": "b", "value": -1}"#),
+            kinesis_record("102", "not json"),
+            kinesis_record("103", r#"{"sensor": "c", "value": 2}"#),
+            kinesis_record("104", r#"{"sensor": "d", "value": 101}"#),
+            kinesis_record("105", r#"{"sensor": "e", "value": 3}"#),
+        ]})).unwrap();
+        let mut kinesis = SrvrlsKinesis::new(ReadingApplication { processed: Vec::new() });
+        let response = kinesis.run(event, Context::default()).unwrap();
+
+        assert_eq!(BatchResponse {
+            batch_item_failures: vec![BatchItemFailure { item_identifier: "104".to_string() }],
+        }, response);
+        assert_eq!(vec!["shardId-000000000006:100:a".to_string(), "shardId-000000000006:103:c".to_string()],
+                   kinesis.application.processed);
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Customer {
+        id: String,
+        visits: i64,
+        balance: f64,
+        active: bool,
+        nickname: Option<String>,
+        tags: Vec<String>,
+        address: Address,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Address {
+        city: String,
+    }
+
+    struct CustomerApplication {
+        changes: Vec<DynamoDbChange<Customer>>,
+    }
+
+    impl SrvrlsDynamoDbApplication for CustomerApplication {
+        type Item = Customer;
+
+        fn handle(&mut self, change: DynamoDbChange<Customer>) -> Result<(), SrvrlsEventError> {
+            match change.event_name.as_str() {
+                "REMOVE" => Err(SrvrlsEventError::Retryable("archive unavailable".to_string())),
+                _ => {
+                    self.changes.push(change);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn dynamodb_record(event_name: &str, sequence_number: &str, new_image: Value) -> Value {
+        json!({
+            "eventID": "c4ca4238a0b923820dcc509a6f75849b",
+            "eventName": event_name,
+            "eventVersion": "1.1",
+            "eventSource": "aws:dynamodb",
+            "awsRegion": "us-east-2",
+            "dynamodb": {
+                "ApproximateCreationDateTime": 1479499740,
+                "Keys": {"id": {"S": "CUST-A23948"}},
+                "NewImage": new_image,
+                "SequenceNumber": sequence_number,
+                "SizeBytes": 26,
+                "StreamViewType": "NEW_IMAGE"
+            },
+            "eventSourceARN": "arn:aws:dynamodb:us-east-2:123456789012:table/customers/stream/2016-11-16T20:42:48.104"
+        })
+    }
+
+    #[test]
+    fn test_dynamodb_change() {
+        let image = json!({
+            "id": {"S": "CUST-A23948"},
+            "visits": {"N": "42"},
+            "balance": {"N": "10.25"},
+            "active": {"BOOL": true},
+            "nickname": {"NULL": true},
+            "tags": {"L": [{"S": "gold"}, {"S": "early"}]},
+            "address": {"M": {"city": {"S": "Seattle"}}}
+        });
+        let event: DynamoDbEvent = serde_json::from_value(json!({"Records": [
+            dynamodb_record("INSERT", "111", image),
+            dynamodb_record("MODIFY", "222", json!({"id": {"S": "CUST-A23948"}})),
+            dynamodb_record("REMOVE", "333", Value::Null),
+            dynamodb_record("INSERT", "444", Value::Null),
+        ]})).unwrap();
+        let mut dynamodb = SrvrlsDynamoDb::new(CustomerApplication { changes: Vec::new() });
+        let response = dynamodb.run(event, Context::default()).unwrap();
+
+        assert_eq!(BatchResponse {
+            batch_item_failures: vec![BatchItemFailure { item_identifier: "333".to_string() }],
+        }, response);
+        assert_eq!(1, dynamodb.application.changes.len());
+        let change = &dynamodb.application.changes[0];
+        assert_eq!(json!({"id": "CUST-A23948"}), change.keys);
+        assert_eq!("111", change.sequence_number);
+        assert_eq!(Some(Utc.timestamp_millis_opt(1479499740000).unwrap()), change.approximate_creation_time);
+        assert_eq!(Some(Customer {
+            id: "CUST-A23948".to_string(),
+            visits: 42,
+            balance: 10.25,
+            active: true,
+            nickname: None,
+            tags: vec!["gold".to_string(), "early".to_string()],
+            address: Address { city: "Seattle".to_string() },
+        }), change.new_image);
+    }
+
+    #[test]
+    fn test_attribute_value() {
+        assert_eq!(json!([1, 2.5]), attribute_value(json!({"NS": ["1", "2.5"]})).unwrap());
+        assert_eq!(json!(["a", "b"]), attribute_value(json!({"SS": ["a", "b"]})).unwrap());
+        assert_eq!(json!("AQI="), attribute_value(json!({"B": "AQI="})).unwrap());
+        assert!(attribute_value(json!({"N": "ten"})).is_err());
+        assert!(attribute_value(json!({"X": "1"})).is_err());
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyRequest;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::application::{Srvrls, SrvrlsApplication};
+use crate::components::SrvrlsError;
+use crate::events::{proxy_request, ProxyRequestParts};
+use crate::invoker::SrvrlsInvoker;
+use crate::request::SrvrlsRequest;
+use crate::response::SrvrlsResponse;
+use crate::websocket::ConnectionPoster;
+
+/// A fluent builder for requests used in testing, this can produce either a `SrvrlsRequest` to test
+/// your application directly or a full API Gateway event to test through `Srvrls`.
+/// ```rust
+/// # use serde::Serialize;
+/// # use srvrls::components::HttpMethod;
+/// # use srvrls::testing::TestRequest;
+/// #[derive(Serialize)]
+/// struct Customer { name: String }
+///
+/// let request = TestRequest::post("customer/CUST-A23948")
+///     .query("notify", "true")
+///     .header("X-Request-Id", "a8f3b2")
+///     .claim("sub", "092ae8e4")
+///     .json_body(&Customer { name: "Steve Smith".to_string() })
+///     .to_request();
+///
+/// assert_eq!(HttpMethod::POST, request.method);
+/// assert_eq!("customer", request.path_parameter(0));
+/// assert_eq!(vec!["true".to_string()], request.query_parameter("notify"));
+/// assert_eq!("application/json", request.header("Content-Type"));
+/// assert_eq!("092ae8e4", request.authentication_claim("sub"));
+/// assert_eq!(r#"{"name":"Steve Smith"}"#, request.body);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TestRequest {
+    method: String,
+    path: String,
+    query_parameters: HashMap<String, Vec<String>>,
+    headers: HashMap<String, String>,
+    claims: Map<String, Value>,
+    body: Option<String>,
+    is_base64_encoded: bool,
+}
+
+impl TestRequest {
+    /// Create a new test request with the provided method and path, the path may be provided
+    /// with or without a leading '/'.
+    #[must_use]
+    pub fn new(method: &str, path: &str) -> Self {
+        TestRequest {
+            method: method.to_string(),
+            path: path.trim_start_matches('/').to_string(),
+            query_parameters: HashMap::new(),
+            headers: HashMap::new(),
+            claims: Map::new(),
+            body: None,
+            is_base64_encoded: false,
+        }
+    }
+
+    /// Create a new `GET` test request.
+    #[must_use]
+    pub fn get(path: &str) -> Self { TestRequest::new("GET", path) }
+
+    /// Create a new `POST` test request.
+    #[must_use]
+    pub fn post(path: &str) -> Self { TestRequest::new("POST", path) }
+
+    /// Create a new `PUT` test request.
+    #[must_use]
+    pub fn put(path: &str) -> Self { TestRequest::new("PUT", path) }
+
+    /// Create a new `DELETE` test request.
+    #[must_use]
+    pub fn delete(path: &str) -> Self { TestRequest::new("DELETE", path) }
+
+    /// Create a new `HEAD` test request.
+    #[must_use]
+    pub fn head(path: &str) -> Self { TestRequest::new("HEAD", path) }
+
+    /// Adds a query parameter, repeated keys add further values.
+    #[must_use]
+    pub fn query(mut self, key: &str, value: &str) -> Self {
+        self.query_parameters.entry(key.to_string()).or_default().push(value.to_string());
+        self
+    }
+
+    /// Adds a request header.
+    #[must_use]
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds a `String` authentication claim.
+    #[must_use]
+    pub fn claim(mut self, claim: &str, value: &str) -> Self {
+        self.claims.insert(claim.to_string(), Value::from(value));
+        self
+    }
+
+    /// Adds a numeric (i64) authentication claim.
+    #[must_use]
+    pub fn integer_claim(mut self, claim: &str, value: i64) -> Self {
+        self.claims.insert(claim.to_string(), Value::from(value));
+        self
+    }
+
+    /// Sets the raw request body.
+    #[must_use]
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = Some(body.to_string());
+        self
+    }
+
+    /// Sets a binary request body, this will be base64 encoded as API Gateway does for binary
+    /// media types.
+    #[must_use]
+    pub fn binary_body(mut self, body: &[u8]) -> Self {
+        self.body = Some(base64::encode(body));
+        self.is_base64_encoded = true;
+        self
+    }
+
+    /// Serializes the provided value as the request body and sets the `Content-Type` to
+    /// `application/json`.
+    ///
+    /// # Panics
+    /// If the value cannot be serialized to JSON.
+    #[must_use]
+    pub fn json_body<T: Serialize>(self, body: &T) -> Self {
+        let body = serde_json::to_string(body).unwrap();
+        self.header("Content-Type", "application/json").body(&body)
+    }
+
+    /// Builds the `SrvrlsRequest` that your application would receive for this request.
+    #[must_use]
+    pub fn to_request(&self) -> SrvrlsRequest {
+        self.to_event().into()
+    }
+
+    /// Builds the API Gateway proxy event for this request, for use with `Srvrls::run`.
+    #[must_use]
+    pub fn to_event(&self) -> ApiGatewayProxyRequest {
+        let mut authorizer = HashMap::new();
+        if !self.claims.is_empty() {
+            authorizer.insert("claims".to_string(), Value::Object(self.claims.clone()));
+        }
+        proxy_request(ProxyRequestParts {
+            path: format!("/{}", self.path),
+            http_method: Some(self.method.clone()),
+            headers: self.headers.clone(),
+            multi_value_headers: HashMap::new(),
+            multi_value_query_string_parameters: self.query_parameters.clone(),
+            authorizer,
+            body: self.body.clone(),
+            is_base64_encoded: self.is_base64_encoded,
+        })
+    }
+}
+
+/// Compares the full API Gateway responses of your application against stored golden files, so that
+/// any change to the responses (e.g., from a header interceptor or error mapping) shows up in
+/// review.
+///
+/// Each `.json` file within the fixtures directory holds one or more recorded events (see
+/// `SrvrlsInvoker`), the responses are compared with the file of the same name in the golden
+/// directory. Set the environment variable `SRVRLS_UPDATE_GOLDEN=1` (or use `update`) to rewrite
+/// the golden files from the current responses.
+/// ```rust
+/// # use srvrls::application::{Srvrls, SrvrlsApplication};
+/// # use srvrls::components::SrvrlsError;
+/// # use srvrls::request::SrvrlsRequest;
+/// # use srvrls::response::SrvrlsResponse;
+/// # use srvrls::testing::GoldenFiles;
+/// # struct App {}
+/// # impl SrvrlsApplication for App {fn handle(&mut self,event: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+/// #         Ok(SrvrlsResponse::ok_empty())
+/// #     }
+/// # }
+/// # let golden_dir = std::env::temp_dir().join("srvrls_golden_doc");
+/// # let _ = std::fs::remove_dir_all(&golden_dir);
+/// # let golden_dir = golden_dir.to_str().unwrap();
+/// GoldenFiles::new("test_data", golden_dir)
+/// #   .update(true)
+///     .assert_matches(Srvrls::new(App {}));
+/// ```
+#[derive(Debug, Clone)]
+pub struct GoldenFiles {
+    fixtures_dir: PathBuf,
+    golden_dir: PathBuf,
+    update: bool,
+}
+
+impl GoldenFiles {
+    /// Create a new golden file comparison for the provided fixtures and golden file directories.
+    #[must_use]
+    pub fn new<P: AsRef<Path>, G: AsRef<Path>>(fixtures_dir: P, golden_dir: G) -> Self {
+        let update = std::env::var("SRVRLS_UPDATE_GOLDEN").map(|v| v == "1" || v == "true").unwrap_or(false);
+        GoldenFiles {
+            fixtures_dir: fixtures_dir.as_ref().to_path_buf(),
+            golden_dir: golden_dir.as_ref().to_path_buf(),
+            update,
+        }
+    }
+
+    /// When set the golden files are rewritten with the current responses rather than compared.
+    #[must_use]
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Runs every fixture through the provided `Srvrls` and compares (or updates) the golden files.
+    ///
+    /// # Panics
+    /// If any fixture cannot be run, a golden file is missing or any response does not match.
+    #[track_caller]
+    pub fn assert_matches<T: SrvrlsApplication>(&self, srvrls: Srvrls<T>) {
+        let mut invoker = SrvrlsInvoker::new(srvrls);
+        let mut failures = Vec::new();
+        for fixture in self.fixtures() {
+            let file_name = fixture.file_name().unwrap();
+            let golden_file = self.golden_dir.join(file_name);
+            let mut responses: Vec<Value> = invoker.invoke_file(&fixture)
+                .unwrap_or_else(|e| panic!("unable to run fixture {}: {}", fixture.display(), e))
+                .iter()
+                .map(|response| serde_json::to_value(response).unwrap())
+                .collect();
+            let actual = match responses.len() {
+                1 => responses.remove(0),
+                _ => Value::Array(responses),
+            };
+            if self.update {
+                fs::create_dir_all(&self.golden_dir).unwrap();
+                fs::write(&golden_file, format!("{}\n", serde_json::to_string_pretty(&actual).unwrap())).unwrap();
+                continue;
+            }
+            match fs::read_to_string(&golden_file) {
+                Err(_) => failures.push(format!("{}: golden file {} not found, run with SRVRLS_UPDATE_GOLDEN=1 to create it",
+                                                fixture.display(), golden_file.display())),
+                Ok(golden) => {
+                    let expected: Value = serde_json::from_str(&golden)
+                        .unwrap_or_else(|e| panic!("golden file {} is not valid JSON: {}", golden_file.display(), e));
+                    if expected != actual {
+                        failures.push(format!("{}: response does not match {}\nexpected:\n{}\nactual:\n{}",
+                                              fixture.display(),
+                                              golden_file.display(),
+                                              serde_json::to_string_pretty(&expected).unwrap(),
+                                              serde_json::to_string_pretty(&actual).unwrap()));
+                    }
+                }
+            }
+        }
+        if !failures.is_empty() {
+            panic!("{} golden file(s) did not match (run with SRVRLS_UPDATE_GOLDEN=1 to update)\n{}",
+                   failures.len(), failures.join("\n"));
+        }
+    }
+
+    fn fixtures(&self) -> Vec<PathBuf> {
+        let entries = fs::read_dir(&self.fixtures_dir)
+            .unwrap_or_else(|e| panic!("unable to read fixtures directory {}: {}", self.fixtures_dir.display(), e));
+        let mut fixtures: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension() == Some("json".as_ref()))
+            .collect();
+        fixtures.sort();
+        fixtures
+    }
+}
+
+impl SrvrlsResponse {
+    /// Asserts that the response has the expected status code.
+    ///
+    /// # Panics
+    /// If the status code does not match.
+    #[track_caller]
+    pub fn assert_status(&self, expected: i32) -> &Self {
+        assert_eq!(expected, self.status_code, "unexpected response status code");
+        self
+    }
+
+    /// Asserts that the response has a header with the expected value, header names are matched
+    /// case-insensitively.
+    ///
+    /// # Panics
+    /// If the header is missing or has a different value.
+    #[track_caller]
+    pub fn assert_header(&self, name: &str, expected: &str) -> &Self {
+        match self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)) {
+            None => panic!("response header '{}' not found in {:?}", name, self.headers),
+            Some((_, value)) => assert_eq!(expected, value, "unexpected value for response header '{}'", name),
+        }
+        self
+    }
+
+    /// Asserts that the response body is JSON equal to the serialized expected value, the
+    /// formatting and field order of the body are ignored.
+    ///
+    /// # Panics
+    /// If the body is missing, is not valid JSON or does not match.
+    #[track_caller]
+    pub fn assert_json_body<T: Serialize>(&self, expected: &T) -> &Self {
+        let expected = serde_json::to_value(expected).unwrap();
+        let actual: Value = match &self.body {
+            None => panic!("response has no body, expected {}", expected),
+            Some(body) => serde_json::from_str(body)
+                .unwrap_or_else(|e| panic!("response body is not valid JSON ({}): {}", e, body)),
+        };
+        assert_eq!(expected, actual, "unexpected response body");
+        self
+    }
+
+    /// Asserts that the response has no body.
+    ///
+    /// # Panics
+    /// If a body is present.
+    #[track_caller]
+    pub fn assert_no_body(&self) -> &Self {
+        assert_eq!(None, self.body, "expected no response body");
+        self
+    }
+}
+
+/// A `ConnectionPoster` that records posted messages in memory, for testing WebSocket applications
+/// without the API Gateway Management API.
+/// ```rust
+/// # use srvrls::components::SrvrlsError;
+/// # use srvrls::testing::InMemoryConnectionPoster;
+/// # use srvrls::websocket::ConnectionPoster;
+/// let mut poster = InMemoryConnectionPoster::new();
+/// poster.with_gone_connection("conn-b");
+///
+/// poster.post("conn-a", b"hello").unwrap();
+/// assert_eq!(Err(SrvrlsError::NotFound), poster.post("conn-b", b"hello"));
+/// assert_eq!(vec!["hello".to_string()], poster.messages_for("conn-a"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryConnectionPoster {
+    messages: Vec<(String, Vec<u8>)>,
+    gone_connections: Vec<String>,
+}
+
+impl InMemoryConnectionPoster {
+    /// Create a new `InMemoryConnectionPoster` with no messages.
+    #[must_use]
+    pub fn new() -> Self {
+        InMemoryConnectionPoster::default()
+    }
+
+    /// Marks a connection as disconnected, posting to it returns `SrvrlsError::NotFound`.
+    pub fn with_gone_connection(&mut self, connection_id: &str) {
+        self.gone_connections.push(connection_id.to_string());
+    }
+
+    /// All posted messages in order, as connection id and data.
+    #[must_use]
+    pub fn messages(&self) -> &[(String, Vec<u8>)] {
+        &self.messages
+    }
+
+    /// The messages posted to a single connection in order, as (lossy) UTF-8 strings.
+    #[must_use]
+    pub fn messages_for(&self, connection_id: &str) -> Vec<String> {
+        self.messages.iter()
+            .filter(|(id, _)| id == connection_id)
+            .map(|(_, data)| String::from_utf8_lossy(data).into_owned())
+            .collect()
+    }
+}
+
+impl ConnectionPoster for InMemoryConnectionPoster {
+    fn post(&mut self, connection_id: &str, data: &[u8]) -> Result<(), SrvrlsError> {
+        if self.gone_connections.iter().any(|id| id == connection_id) {
+            return Err(SrvrlsError::NotFound);
+        }
+        self.messages.push((connection_id.to_string(), data.to_vec()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod testing_tests {
+    use serde_json::json;
+
+    use crate::components::HttpMethod;
+
+    use super::*;
+
+    #[test]
+    fn test_to_request() {
+        let request = TestRequest::get("/customer/CUST-A23948")
+            .query("tag", "a")
+            .query("tag", "b")
+            .header("Accept", "application/json")
+            .claim("sub", "user-1")
+            .integer_claim("auth_time", 1_582_147_631)
+            .to_request();
+
+        assert_eq!(HttpMethod::GET, request.method);
+        assert_eq!("customer/CUST-A23948", request.path);
+        assert_eq!(vec!["a".to_string(), "b".to_string()], request.query_parameter("tag"));
+        assert_eq!("application/json", request.header("accept"));
+        assert_eq!("user-1", request.authentication_claim("sub"));
+        assert_eq!(Some(&1_582_147_631), request.integer_claims.get("auth_time"));
+        assert_eq!("", request.body);
+    }
+
+    #[test]
+    fn test_to_event() {
+        let event = TestRequest::put("customer").json_body(&json!({"id": "CUST-A23948"})).to_event();
+
+        assert_eq!(Some("PUT".to_string()), event.http_method);
+        assert_eq!(Some("/customer".to_string()), event.path);
+        assert_eq!("customer", event.path_parameters["proxy"]);
+        assert_eq!(Some(r#"{"id":"CUST-A23948"}"#.to_string()), event.body);
+        assert!(event.request_context.authorizer.is_empty());
+    }
+
+    #[test]
+    fn test_assertions() {
+        let mut response = SrvrlsResponse::ok(json!({"id": "CUST-A23948", "name": "Steve Smith"}));
+        response.headers.insert("Content-Type".to_string(), "application/json".to_string());
+
+        response.assert_status(200)
+            .assert_header("content-type", "application/json")
+            .assert_json_body(&json!({"name": "Steve Smith", "id": "CUST-A23948"}));
+        SrvrlsResponse::no_content().assert_status(204).assert_no_body();
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected response body")]
+    fn test_assert_json_body_mismatch() {
+        SrvrlsResponse::ok(json!({"id": "CUST-A23948"})).assert_json_body(&json!({"id": "CUST-B00000"}));
+    }
+
+    struct NotFoundApplication {}
+
+    impl SrvrlsApplication for NotFoundApplication {
+        fn handle(&mut self, _event: SrvrlsRequest) -> Result<SrvrlsResponse, crate::components::SrvrlsError> {
+            Err(crate::components::SrvrlsError::NotFound)
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "golden file(s) did not match")]
+    fn test_golden_files_mismatch() {
+        let golden_dir = std::env::temp_dir().join("srvrls_golden_mismatch");
+        let _ = fs::remove_dir_all(&golden_dir);
+        GoldenFiles::new("test_data", &golden_dir).update(true).assert_matches(Srvrls::new(NotFoundApplication {}));
+        GoldenFiles::new("test_data", &golden_dir).update(false).assert_matches(Srvrls::new(NotFoundApplication {}));
+
+        fs::write(golden_dir.join("happy_path_event.json"), r#"{"statusCode":200}"#).unwrap();
+        GoldenFiles::new("test_data", &golden_dir).update(false).assert_matches(Srvrls::new(NotFoundApplication {}));
+    }
+}
@@ -0,0 +1,86 @@
+use lambda_runtime::Context;
+use log::info;
+use serde_json::{json, Value};
+
+/// Configuration of the warm-up (keep-alive) pings that are answered without calling your
+/// application, see `Srvrls::with_warm_up`.
+///
+/// An event is a warm-up ping if it matches any of the payloads, i.e., it holds every field of the
+/// payload with the same value. An empty object only matches an empty event. By default the
+/// `serverless-plugin-warmup` payload, scheduled EventBridge events and empty events (e.g., a
+/// schedule with a constant `{}` input) are recognized.
+/// ```rust
+/// # use serde_json::json;
+/// # use srvrls::warmup::WarmUp;
+/// let mut warm_up = WarmUp::default();
+/// warm_up.payloads.push(json!({"keepAlive": true}));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarmUp {
+    /// The payloads identifying a warm-up ping.
+    pub payloads: Vec<Value>,
+}
+
+impl Default for WarmUp {
+    fn default() -> Self {
+        WarmUp {
+            payloads: vec![
+                json!({"source": "serverless-plugin-warmup"}),
+                json!({"source": "aws.events", "detail-type": "Scheduled Event"}),
+                json!({}),
+            ],
+        }
+    }
+}
+
+impl WarmUp {
+    /// Whether the raw event is a warm-up ping.
+    pub(crate) fn matches(&self, event: &Value) -> bool {
+        self.payloads.iter().any(|payload| contains(event, payload))
+    }
+}
+
+/// Logs a warm-up ping along with the execution environment (log stream) that received it and,
+/// for `serverless-plugin-warmup` pings, the concurrency slot, e.g., `warm-up 2 of 5`.
+pub(crate) fn log_warm_up(event: &Value, ctx: &Context) {
+    match (event["__WARMER_INVOCATION__"].as_u64(), event["__WARMER_CONCURRENCY__"].as_u64()) {
+        (Some(invocation), Some(concurrency)) => info!("warm-up {} of {} received by {}, correlation id {}",
+                                                      invocation, concurrency, ctx.log_stream_name,
+                                                      event["__WARMER_CORRELATIONID__"].as_str().unwrap_or("")),
+        _ => info!("warm-up received by {}", ctx.log_stream_name),
+    }
+}
+
+fn contains(event: &Value, payload: &Value) -> bool {
+    match (event, payload) {
+        (Value::Object(event), Value::Object(payload)) if payload.is_empty() => event.is_empty(),
+        (Value::Object(event), Value::Object(payload)) => payload.iter()
+            .all(|(k, v)| event.get(k).is_some_and(|value| contains(value, v))),
+        (event, payload) => event == payload,
+    }
+}
+
+#[cfg(test)]
+mod warmup_tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let warm_up = WarmUp::default();
+        assert!(warm_up.matches(&json!({"source": "serverless-plugin-warmup", "__WARMER_INVOCATION__": 1, "__WARMER_CONCURRENCY__": 2})));
+        assert!(warm_up.matches(&json!({"source": "aws.events", "detail-type": "Scheduled Event", "detail": {}})));
+        assert!(warm_up.matches(&json!({})));
+
+        assert!(!warm_up.matches(&json!({"source": "com.example.orders", "detail-type": "Scheduled Event"})));
+        assert!(!warm_up.matches(&json!({"httpMethod": "GET", "path": "/orders", "requestContext": {}})));
+        assert!(!warm_up.matches(&json!("serverless-plugin-warmup")));
+    }
+
+    #[test]
+    fn test_nested_payload() {
+        let warm_up = WarmUp { payloads: vec![json!({"detail": {"keepAlive": true}})] };
+        assert!(warm_up.matches(&json!({"source": "scheduler", "detail": {"keepAlive": true, "slot": 3}})));
+        assert!(!warm_up.matches(&json!({"detail": {"keepAlive": false}})));
+        assert!(!warm_up.matches(&json!({})));
+    }
+}
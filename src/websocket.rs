@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+
+use aws_lambda_events::event::apigw::ApiGatewayProxyResponse;
+use lambda_runtime::{Context, Handler};
+use lambda_runtime::error::HandlerError;
+use log::warn;
+
+use crate::application::error_status_and_body;
+use crate::components::SrvrlsError;
+use crate::events::{proxy_request, ProxyRequestParts, WebSocketRequest};
+use crate::request::SrvrlsRequest;
+use crate::response::SrvrlsResponse;
+
+/// The connection metadata of a WebSocket API request.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WebSocketConnection {
+    /// The id of the connection, used to post messages back to the client.
+    pub connection_id: String,
+    /// The route key that matched the request, e.g. `$connect` or `sendMessage`.
+    pub route_key: String,
+    /// The event type, one of `CONNECT`, `DISCONNECT` or `MESSAGE`.
+    pub event_type: String,
+    /// Epoch time of the connection in milliseconds.
+    pub connected_at: i64,
+    /// The domain name used to connect to the API.
+    pub domain_name: String,
+    /// The deployed stage.
+    pub stage: String,
+    /// The message id, or an empty string for `$connect` and `$disconnect`.
+    pub message_id: String,
+}
+
+/// Posts messages to connected WebSocket clients, for AWS this is the API Gateway Management API
+/// `@connections` endpoint of the API. See `testing::InMemoryConnectionPoster` for testing.
+pub trait ConnectionPoster {
+    /// Posts the data to the client with this connection id.
+    ///
+    /// # Errors
+    /// A `SrvrlsError::NotFound` is returned if the client is no longer connected.
+    fn post(&mut self, connection_id: &str, data: &[u8]) -> Result<(), SrvrlsError>;
+}
+
+/// This trait should be implemented by your application to handle WebSocket API requests, the
+/// `$connect` and `$disconnect` routes are passed to `connect` and `disconnect`, all other routes
+/// (including `$default`) are passed to `message` with their' route key.
+/// ```rust
+/// # use srvrls::components::SrvrlsError;
+/// # use srvrls::request::SrvrlsRequest;
+/// # use srvrls::response::SrvrlsResponse;
+/// # use srvrls::websocket::{ConnectionPoster, SrvrlsWebSocketApplication, WebSocketConnection};
+/// struct ChatApplication {
+///     connections: Vec<String>,
+/// }
+///
+/// impl SrvrlsWebSocketApplication for ChatApplication {
+///     fn connect(&mut self, connection: &WebSocketConnection, _request: SrvrlsRequest, _poster: &mut dyn ConnectionPoster) -> Result<SrvrlsResponse, SrvrlsError> {
+///         self.connections.push(connection.connection_id.clone());
+///         Ok(SrvrlsResponse::ok_empty())
+///     }
+///
+///     fn message(&mut self, route_key: &str, _connection: &WebSocketConnection, request: SrvrlsRequest, poster: &mut dyn ConnectionPoster) -> Result<SrvrlsResponse, SrvrlsError> {
+///         match route_key {
+///             "sendMessage" => {
+///                 for connection_id in &self.connections {
+///                     poster.post(connection_id, request.body.as_bytes())?;
+///                 }
+///                 Ok(SrvrlsResponse::ok_empty())
+///             }
+///             _ => Err(SrvrlsError::NotFound),
+///         }
+///     }
+/// }
+/// ```
+pub trait SrvrlsWebSocketApplication {
+    /// This method receives `$connect` requests, any response other than a 2xx rejects the
+    /// connection. By default all connections are accepted.
+    ///
+    /// # Errors
+    /// Errors returned by this method will be mapped to a (4xx or 5xx) response.
+    fn connect(&mut self, _connection: &WebSocketConnection, _request: SrvrlsRequest, _poster: &mut dyn ConnectionPoster) -> Result<SrvrlsResponse, SrvrlsError> {
+        Ok(SrvrlsResponse::ok_empty())
+    }
+
+    /// This method receives `$disconnect` requests, the client is already disconnected so no
+    /// response is sent.
+    ///
+    /// # Errors
+    /// Errors returned by this method are logged.
+    fn disconnect(&mut self, _connection: &WebSocketConnection, _poster: &mut dyn ConnectionPoster) -> Result<(), SrvrlsError> {
+        Ok(())
+    }
+
+    /// This method receives messages for all other route keys, the response body is only sent to
+    /// the client if the route has a route response configured.
+    ///
+    /// # Errors
+    /// Errors returned by this method will be mapped to a (4xx or 5xx) response.
+    fn message(&mut self, route_key: &str, connection: &WebSocketConnection, request: SrvrlsRequest, poster: &mut dyn ConnectionPoster) -> Result<SrvrlsResponse, SrvrlsError>;
+}
+
+/// `SrvrlsWebSocket` wraps your application that implements `SrvrlsWebSocketApplication` along
+/// with the `ConnectionPoster` used to push messages, and interfaces with AWS Lambda for WebSocket
+/// API requests.
+/// ```ignore
+/// use lambda_runtime::lambda;
+/// fn main() -> Result<(), Box<dyn Error>> {
+///     let websocket = SrvrlsWebSocket::new(ChatApplication { connections: Vec::new() }, ManagementApiPoster::new());
+///     lambda!(websocket);
+///     Ok(())
+/// }
+/// ```
+pub struct SrvrlsWebSocket<T: SrvrlsWebSocketApplication, P: ConnectionPoster> {
+    application: T,
+    poster: P,
+}
+
+impl<T: SrvrlsWebSocketApplication, P: ConnectionPoster> SrvrlsWebSocket<T, P> {
+    /// Create a new `SrvrlsWebSocket` instance to interface with AWS Lambda.
+    pub fn new(application: T, poster: P) -> Self {
+        SrvrlsWebSocket { application, poster }
+    }
+
+    /// The `ConnectionPoster` used by the application.
+    #[must_use]
+    pub fn poster(&self) -> &P {
+        &self.poster
+    }
+
+    fn dispatch(&mut self, connection: &WebSocketConnection, request: SrvrlsRequest) -> Result<SrvrlsResponse, SrvrlsError> {
+        match connection.route_key.as_str() {
+            "$connect" => self.application.connect(connection, request, &mut self.poster),
+            "$disconnect" => {
+                if let Err(e) = self.application.disconnect(connection, &mut self.poster) {
+                    warn!("disconnect of {} failed: {}", connection.connection_id, e);
+                }
+                Ok(SrvrlsResponse::ok_empty())
+            }
+            route_key => self.application.message(route_key, connection, request, &mut self.poster),
+        }
+    }
+}
+
+impl<T: SrvrlsWebSocketApplication, P: ConnectionPoster> Handler<WebSocketRequest, ApiGatewayProxyResponse, HandlerError> for SrvrlsWebSocket<T, P> {
+    fn run(&mut self, event: WebSocketRequest, _ctx: Context) -> Result<ApiGatewayProxyResponse, HandlerError> {
+        let context = event.request_context;
+        let connection = WebSocketConnection {
+            connection_id: context.connection_id.unwrap_or_default(),
+            route_key: context.route_key.unwrap_or_default(),
+            event_type: context.event_type.unwrap_or_default(),
+            connected_at: context.connected_at,
+            domain_name: context.domain_name.unwrap_or_default(),
+            stage: context.stage.unwrap_or_default(),
+            message_id: context.message_id.unwrap_or_default(),
+        };
+        let request: SrvrlsRequest = proxy_request(ProxyRequestParts {
+            path: "".to_string(),
+            http_method: None,
+            headers: event.headers,
+            multi_value_headers: event.multi_value_headers,
+            multi_value_query_string_parameters: event.multi_value_query_string_parameters,
+            authorizer: context.authorizer,
+            body: event.body,
+            is_base64_encoded: event.is_base64_encoded,
+        }).into();
+        let (status_code, headers, body, is_base64_encoded) = match self.dispatch(&connection, request) {
+            Ok(response) => (i64::from(response.status_code), response.headers, response.body, response.is_base64_encoded),
+            Err(e) => {
+                let (status_code, body) = error_status_and_body(e)?;
+                (status_code, HashMap::new(), body, false)
+            }
+        };
+        Ok(ApiGatewayProxyResponse {
+            status_code,
+            headers,
+            multi_value_headers: HashMap::new(),
+            body,
+            is_base64_encoded: Some(is_base64_encoded).filter(|encoded| *encoded),
+        })
+    }
+}
+
+#[cfg(test)]
+mod websocket_tests {
+    use serde_json::json;
+
+    use crate::testing::InMemoryConnectionPoster;
+
+    use super::*;
+
+    struct ChatApplication {
+        connections: Vec<String>,
+        disconnected: Vec<String>,
+    }
+
+    impl SrvrlsWebSocketApplication for ChatApplication {
+        fn connect(&mut self, connection: &WebSocketConnection, request: SrvrlsRequest, _poster: &mut dyn ConnectionPoster) -> Result<SrvrlsResponse, SrvrlsError> {
+            match request.query_parameter("room").first().map(String::as_str) {
+                Some("lobby") => {
+                    self.connections.push(connection.connection_id.clone());
+                    Ok(SrvrlsResponse::ok_empty())
+                }
+                _ => Err(SrvrlsError::Forbidden),
+            }
+        }
+
+        fn disconnect(&mut self, connection: &WebSocketConnection, _poster: &mut dyn ConnectionPoster) -> Result<(), SrvrlsError> {
+            self.disconnected.push(connection.connection_id.clone());
+            Err(SrvrlsError::InternalServerError)
+        }
+
+        fn message(&mut self, route_key: &str, connection: &WebSocketConnection, request: SrvrlsRequest, poster: &mut dyn ConnectionPoster) -> Result<SrvrlsResponse, SrvrlsError> {
+            match route_key {
+                "sendMessage" => {
+                    for connection_id in &self.connections {
+                        poster.post(connection_id, format!("{}: {}", connection.connection_id, request.body).as_bytes())?;
+                    }
+                    Ok(SrvrlsResponse::ok(json!({"sent": self.connections.len()})))
+                }
+                _ => Err(SrvrlsError::BadRequestWithSimpleMessage(format!("unknown action on {}", connection.stage))),
+            }
+        }
+    }
+
+    fn event(route_key: &str, event_type: &str, connection_id: &str, body: Option<&str>) -> WebSocketRequest {
+        serde_json::from_value(json!({
+            "headers": {"Host": "abc123.execute-api.us-east-1.amazonaws.com"},
+            "multiValueQueryStringParameters": {"room": ["lobby"]},
+            "requestContext": {
+                "routeKey": route_key,
+                "eventType": event_type,
+                "messageId": "Vr9ZqfhwoAMCJ-A=",
+                "connectionId": connection_id,
+                "connectedAt": 1603042357000_i64,
+                "domainName": "abc123.execute-api.us-east-1.amazonaws.com",
+                "stage": "production",
+                "apiId": "abc123",
+                "requestId": "Vr9ZqG5uIAMF9gA="
+            },
+            "body": body,
+            "isBase64Encoded": false
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_route_dispatch() {
+        let application = ChatApplication { connections: Vec::new(), disconnected: Vec::new() };
+        let mut websocket = SrvrlsWebSocket::new(application, InMemoryConnectionPoster::new());
+
+        let response = websocket.run(event("$connect", "CONNECT", "conn-a", None), Context::default()).unwrap();
+        assert_eq!(200, response.status_code);
+        websocket.run(event("$connect", "CONNECT", "conn-b", None), Context::default()).unwrap();
+
+        let response = websocket.run(event("sendMessage", "MESSAGE", "conn-a", Some("hello")), Context::default()).unwrap();
+        assert_eq!(200, response.status_code);
+        assert_eq!(Some(r#"{"sent":2}"#.to_string()), response.body);
+        assert_eq!(vec!["conn-a: hello".to_string()], websocket.poster().messages_for("conn-b"));
+        assert_eq!(vec!["conn-a: hello".to_string()], websocket.poster().messages_for("conn-a"));
+
+        let response = websocket.run(event("$default", "MESSAGE", "conn-a", Some("?")), Context::default()).unwrap();
+        assert_eq!(400, response.status_code);
+        assert_eq!(Some(r#"{"error":"unknown action on production"}"#.to_string()), response.body);
+
+        let response = websocket.run(event("$disconnect", "DISCONNECT", "conn-b", None), Context::default()).unwrap();
+        assert_eq!(200, response.status_code);
+        assert_eq!(vec!["conn-b".to_string()], websocket.application.disconnected);
+    }
+
+    #[test]
+    fn test_gone_connection() {
+        let application = ChatApplication { connections: vec!["conn-a".to_string()], disconnected: Vec::new() };
+        let mut poster = InMemoryConnectionPoster::new();
+        poster.with_gone_connection("conn-a");
+        let mut websocket = SrvrlsWebSocket::new(application, poster);
+
+        let response = websocket.run(event("sendMessage", "MESSAGE", "conn-b", Some("hello")), Context::default()).unwrap();
+        assert_eq!(404, response.status_code);
+        assert!(websocket.poster().messages().is_empty());
+    }
+}